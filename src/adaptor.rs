@@ -0,0 +1,27 @@
+//! Configuration of `Requester` adaptors (like `Throttle` or `CacheMe`).
+//!
+//! Adaptors implement some methods by hand and forward all other methods to
+//! the inner requester, see [`gen::adaptor`](crate::gen::adaptor).
+
+use std::path::{Path, PathBuf};
+
+use crate::{error::Result, schema::load_ron};
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Adaptor {
+    /// Name of the adaptor type, e.g. `Throttle`.
+    ///
+    /// The type must have a single generic parameter, the inner requester.
+    pub name: String,
+    /// Path of the file with the adaptor, relative to the output directory.
+    pub path: PathBuf,
+    /// Methods (`snake_case`) that are implemented by hand.
+    #[serde(default)]
+    pub overrides: Vec<String>,
+}
+
+/// Loads a RON list of adaptors.
+pub fn load(path: &Path) -> Result<Vec<Adaptor>> {
+    load_ron(path)
+}
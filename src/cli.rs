@@ -0,0 +1,399 @@
+use std::{fmt, path::PathBuf};
+
+use cg::{keyword::Escape, output::Mode};
+
+pub const USAGE: &str = "\
+cg — code generator for teloxide-core
+
+USAGE:
+    cg <COMMAND> [OPTIONS]
+    cg diff <OLD> <NEW> [--json]
+
+COMMANDS:
+    payloads             Generate payload files, `payloads/{method}.rs`
+    payloads-mod         Generate `payloads.rs` and `payloads/setters.rs`
+    requester            Generate `requester.rs` with the `Requester` trait
+    requester-forward    Generate `requester_forward.rs` with the `requester_forward!` macro
+    requester-impls      Generate `requester_impls.rs` with `Requester` impls for `&B`,
+                         `Box<B>` and `Arc<B>`
+    bot                  Generate `bot/api.rs` with the `Requester` impl for `Bot`
+    erased               Generate `adaptors/erased/requester.rs` with the object-safe
+                         `ErasableRequester` trait
+    api-version          Generate `bot_api_version.rs` with `BOT_API_VERSION` and
+                         `BOT_API_DATE` constants
+    adaptors             Generate `Requester` impls for adaptors from `--adaptors`
+    all                  All of the above (adaptors only if `--adaptors` is set)
+    validate             Only check the schema for errors
+    diff                 Compare two schemas (e.g. of different Bot API versions) and
+                         print added, removed and changed methods and parameters
+    help                 Print this message
+
+OPTIONS:
+    -s, --schema <PATH>    Path to the schema [env: SC_PATH]
+    -p, --patches <PATH>   Path to the patch file, if not set, patches bundled with cg
+                           are used [env: PATCHES_PATH]
+    -a, --adaptors <PATH>  Path to a RON file with a list of adaptors:
+                           `(name: \"Throttle\", path: \"adaptors/throttle.rs\",
+                           overrides: [\"send_message\"])`, methods that are not
+                           overridden are forwarded to the inner requester
+    -o, --out <PATH>       Output directory (usually `teloxide-core/src`),
+                           if not set, generated files are printed to stdout
+        --splice           For partly generated files (`payloads.rs`, `requester.rs`,
+                           `requester_forward.rs`, `bot/api.rs`, adaptors) replace only
+                           the code between `// START AUTO-GENERATED` and
+                           `// END AUTO-GENERATED` markers in the existing file
+                           (existing adaptor files are always spliced, new ones are
+                           created from a template)
+        --strict           Fail if some patches didn't change anything (instead of
+                           only printing a warning)
+        --check            Don't write anything, instead compare generated code with
+                           files in `--out`, print the diff and fail if they differ
+        --escape <STYLE>   How to escape parameter names that are Rust keywords:
+                           `suffix` (`type_`, default) or `raw` (`r#type`)
+        --json             Print `diff` as JSON
+    -h, --help             Print this message
+
+For compatibility with old scripts, the command may also be selected with
+the `ACTION` environment variable (`0` = payloads, `1` = payloads-mod,
+`2` = requester, `3` = requester-forward). For `payloads`, `PL_PATH` is
+treated as the path to the `payloads` directory, i.e. payload files are
+written right into it.";
+
+/// Exit code used when the command line can't be parsed.
+pub const EXIT_USAGE: i32 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    Payloads,
+    PayloadsMod,
+    Requester,
+    RequesterForward,
+    RequesterImpls,
+    Bot,
+    Erased,
+    ApiVersion,
+    Adaptors,
+    All,
+    Validate,
+    Diff,
+    Help,
+}
+
+impl Command {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "payloads" => Some(Command::Payloads),
+            "payloads-mod" => Some(Command::PayloadsMod),
+            "requester" => Some(Command::Requester),
+            "requester-forward" => Some(Command::RequesterForward),
+            "requester-impls" => Some(Command::RequesterImpls),
+            "bot" => Some(Command::Bot),
+            "erased" => Some(Command::Erased),
+            "api-version" => Some(Command::ApiVersion),
+            "adaptors" => Some(Command::Adaptors),
+            "all" => Some(Command::All),
+            "validate" => Some(Command::Validate),
+            "diff" => Some(Command::Diff),
+            "help" => Some(Command::Help),
+            _ => None,
+        }
+    }
+
+    /// Old `ACTION` codes.
+    fn from_action(action: &str) -> Option<Self> {
+        match action {
+            "0" => Some(Command::Payloads),
+            "1" => Some(Command::PayloadsMod),
+            "2" => Some(Command::Requester),
+            "3" => Some(Command::RequesterForward),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Args {
+    pub command: Command,
+    pub schema: Option<PathBuf>,
+    pub patches: Option<PathBuf>,
+    pub adaptors: Option<PathBuf>,
+    pub out: Option<PathBuf>,
+    /// `out` is the `payloads` directory itself (set from `PL_PATH`), so
+    /// payload files are written without the `payloads/` prefix.
+    pub payloads_out: bool,
+    pub mode: Mode,
+    /// Only check that files are up to date.
+    pub check: bool,
+    /// Treat unused patches as errors.
+    pub strict: bool,
+    pub escape: Escape,
+    /// `(old, new)` schemas for `diff`.
+    pub diff: Option<(PathBuf, PathBuf)>,
+    /// Print `diff` as JSON.
+    pub json: bool,
+}
+
+#[derive(Debug)]
+pub enum UsageError {
+    NoCommand,
+    UnknownCommand(String),
+    UnknownAction(String),
+    UnknownOption(String),
+    MissingValue(&'static str),
+    InvalidValue(&'static str, String),
+    Missing(&'static str),
+    Unexpected(String),
+}
+
+impl fmt::Display for UsageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UsageError::NoCommand => f.write_str("no command given"),
+            UsageError::UnknownCommand(c) => write!(f, "unknown command `{}`", c),
+            UsageError::UnknownAction(a) => write!(f, "unknown `ACTION` value `{}`", a),
+            UsageError::UnknownOption(o) => write!(f, "unknown option `{}`", o),
+            UsageError::MissingValue(o) => write!(f, "option `{}` requires a value", o),
+            UsageError::InvalidValue(o, v) => write!(f, "invalid value `{}` for `{}`", v, o),
+            UsageError::Missing(what) => write!(f, "{} is required", what),
+            UsageError::Unexpected(arg) => write!(f, "unexpected argument `{}`", arg),
+        }
+    }
+}
+
+impl Args {
+    /// Parses arguments of the current process, falling back to the old
+    /// `SC_PATH`/`ACTION`/`PL_PATH` environment variables.
+    pub fn from_env() -> Result<Self, UsageError> {
+        Self::parse(std::env::args().skip(1), |var| std::env::var(var).ok())
+    }
+
+    pub fn parse(
+        args: impl IntoIterator<Item = String>,
+        env: impl Fn(&str) -> Option<String>,
+    ) -> Result<Self, UsageError> {
+        let mut command = None;
+        let mut schema = None;
+        let mut patches = None;
+        let mut adaptors = None;
+        let mut out = None;
+        let mut mode = Mode::Overwrite;
+        let mut check = false;
+        let mut strict = false;
+        let mut escape = Escape::Suffix;
+        let mut json = false;
+        let mut diff = Vec::new();
+
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let (name, inline_value) = match arg.find('=') {
+                Some(i) if arg.starts_with("--") => (&arg[..i], Some(arg[i + 1..].to_owned())),
+                _ => (&*arg, None),
+            };
+
+            let mut value = |option: &'static str| {
+                inline_value
+                    .clone()
+                    .or_else(|| args.next())
+                    .ok_or(UsageError::MissingValue(option))
+            };
+
+            match name {
+                "-s" | "--schema" => schema = Some(PathBuf::from(value("--schema")?)),
+                "-p" | "--patches" => patches = Some(PathBuf::from(value("--patches")?)),
+                "-a" | "--adaptors" => adaptors = Some(PathBuf::from(value("--adaptors")?)),
+                "-o" | "--out" => out = Some(PathBuf::from(value("--out")?)),
+                "--splice" => mode = Mode::Splice,
+                "--check" => check = true,
+                "--strict" => strict = true,
+                "--json" => json = true,
+                "--escape" => {
+                    let style = value("--escape")?;
+                    escape = Escape::from_name(&style)
+                        .ok_or(UsageError::InvalidValue("--escape", style))?;
+                }
+                "-h" | "--help" => command = Some(Command::Help),
+                opt if opt.starts_with('-') => return Err(UsageError::UnknownOption(arg)),
+                name if command.is_none() => {
+                    command = Some(
+                        Command::from_name(name)
+                            .ok_or_else(|| UsageError::UnknownCommand(arg.clone()))?,
+                    )
+                }
+                _ if command == Some(Command::Diff) && diff.len() < 2 => {
+                    diff.push(PathBuf::from(arg))
+                }
+                _ => return Err(UsageError::Unexpected(arg)),
+            }
+        }
+
+        let command = match command {
+            Some(command) => command,
+            None => match env("ACTION") {
+                Some(action) => {
+                    Command::from_action(&action).ok_or(UsageError::UnknownAction(action))?
+                }
+                None => return Err(UsageError::NoCommand),
+            },
+        };
+
+        let schema = schema.or_else(|| env("SC_PATH").map(PathBuf::from));
+        let patches = patches.or_else(|| env("PATCHES_PATH").map(PathBuf::from));
+        // `PL_PATH` was only used by payloads, other actions print code to
+        // stdout even if it's set
+        let pl_path = match (&out, command) {
+            (None, Command::Payloads) => env("PL_PATH").map(PathBuf::from),
+            _ => None,
+        };
+        let payloads_out = pl_path.is_some();
+        let out = out.or(pl_path);
+
+        let diff = match (command, &*diff) {
+            (Command::Diff, [old, new]) => Some((old.clone(), new.clone())),
+            (Command::Diff, _) => {
+                return Err(UsageError::Missing(
+                    "`<OLD>` and `<NEW>` schemas with `diff`",
+                ))
+            }
+            _ => None,
+        };
+
+        if command != Command::Help && command != Command::Diff && schema.is_none() {
+            return Err(UsageError::Missing("`--schema` (or `SC_PATH`)"));
+        }
+
+        if command == Command::Adaptors && adaptors.is_none() {
+            return Err(UsageError::Missing("`--adaptors` with `adaptors`"));
+        }
+
+        if check && out.is_none() {
+            return Err(UsageError::Missing("`--out` (or `PL_PATH`) with `--check`"));
+        }
+
+        Ok(Args {
+            command,
+            schema,
+            patches,
+            adaptors,
+            out,
+            payloads_out,
+            mode,
+            check,
+            strict,
+            escape,
+            diff,
+            json,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::{Args, Command, UsageError};
+
+    fn parse(args: &[&str], env: &[(&str, &str)]) -> Result<Args, UsageError> {
+        Args::parse(args.iter().map(|&a| a.to_owned()), |var| {
+            env.iter()
+                .find(|(name, _)| *name == var)
+                .map(|(_, value)| (*value).to_owned())
+        })
+    }
+
+    #[test]
+    fn action() {
+        let actions = [
+            ("0", Command::Payloads),
+            ("1", Command::PayloadsMod),
+            ("2", Command::Requester),
+            ("3", Command::RequesterForward),
+        ];
+
+        for &(action, command) in &actions {
+            let env = [
+                ("ACTION", action),
+                ("SC_PATH", "schema.ron"),
+                ("PL_PATH", "core/src/payloads"),
+            ];
+            let args = parse(&[], &env).unwrap();
+
+            assert_eq!(args.command, command);
+            assert_eq!(args.schema, Some(PathBuf::from("schema.ron")));
+
+            // `PL_PATH` is only used for payloads, other actions print to stdout
+            let out = match command {
+                Command::Payloads => Some(PathBuf::from("core/src/payloads")),
+                _ => None,
+            };
+            assert_eq!(args.out, out);
+            assert_eq!(args.payloads_out, command == Command::Payloads);
+        }
+    }
+
+    #[test]
+    fn current_dir_payloads() {
+        let env = [("ACTION", "0"), ("SC_PATH", "schema.ron"), ("PL_PATH", ".")];
+        let args = parse(&[], &env).unwrap();
+
+        assert_eq!(args.out, Some(PathBuf::from(".")));
+        assert!(args.payloads_out);
+    }
+
+    #[test]
+    fn out_overrides_pl_path() {
+        let env = [("PL_PATH", "core/src/payloads")];
+        let args = parse(&["payloads", "-s", "s.ron", "-o", "src"], &env).unwrap();
+
+        assert_eq!(args.out, Some(PathBuf::from("src")));
+        assert!(!args.payloads_out);
+    }
+
+    #[test]
+    fn unknown_action() {
+        let env = [("ACTION", "4"), ("SC_PATH", "schema.ron")];
+
+        assert!(matches!(
+            parse(&[], &env),
+            Err(UsageError::UnknownAction(a)) if a == "4"
+        ));
+    }
+
+    #[test]
+    fn options_override_env() {
+        let env = [("ACTION", "0"), ("SC_PATH", "env.ron")];
+        let args = parse(&["requester", "--schema", "arg.ron"], &env).unwrap();
+
+        assert_eq!(args.command, Command::Requester);
+        assert_eq!(args.schema, Some(PathBuf::from("arg.ron")));
+    }
+
+    #[test]
+    fn inline_values() {
+        let args = parse(&["all", "--schema=s.ron", "--out=src", "--check"], &[]).unwrap();
+
+        assert_eq!(args.schema, Some(PathBuf::from("s.ron")));
+        assert_eq!(args.out, Some(PathBuf::from("src")));
+        assert!(args.check);
+    }
+
+    #[test]
+    fn missing_schema() {
+        assert!(matches!(parse(&["all"], &[]), Err(UsageError::Missing(_))));
+    }
+
+    #[test]
+    fn check_without_out() {
+        assert!(matches!(
+            parse(&["all", "-s", "s.ron", "--check"], &[]),
+            Err(UsageError::Missing(_))
+        ));
+    }
+
+    #[test]
+    fn missing_value() {
+        assert!(matches!(
+            parse(&["all", "--schema"], &[]),
+            Err(UsageError::MissingValue("--schema"))
+        ));
+    }
+}
@@ -0,0 +1,353 @@
+//! Structural diff between two schemas, e.g. between two Bot API versions.
+//!
+//! Methods are matched by their `camelCase` names and parameters by their
+//! names, so the diff isn't affected by reordering.
+
+use std::fmt;
+
+use crate::schema::{Doc, Method, Param, Schema, Type};
+
+#[derive(Debug, serde::Serialize)]
+pub struct SchemaDiff {
+    /// `(old, new)` versions, if they differ.
+    pub api_version: Option<(String, String)>,
+    pub added_methods: Vec<String>,
+    pub removed_methods: Vec<String>,
+    pub changed_methods: Vec<MethodDiff>,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct MethodDiff {
+    /// `camelCase` name of the method.
+    pub method: String,
+    pub changes: Vec<Change>,
+}
+
+#[derive(Debug, serde::Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Change {
+    ReturnType {
+        old: String,
+        new: String,
+    },
+    /// Texts (here and in `Notes` and `ParamDoc`) include definitions of
+    /// links.
+    Doc {
+        old: String,
+        new: String,
+    },
+    Notes {
+        old: Vec<String>,
+        new: Vec<String>,
+    },
+    ParamAdded {
+        param: String,
+        ty: String,
+    },
+    ParamRemoved {
+        param: String,
+        ty: String,
+    },
+    ParamRetyped {
+        param: String,
+        old: String,
+        new: String,
+    },
+    /// The parameter became optional (`optional == true`) or required.
+    ParamOptionality {
+        param: String,
+        optional: bool,
+    },
+    ParamDoc {
+        param: String,
+        old: String,
+        new: String,
+    },
+}
+
+impl SchemaDiff {
+    pub fn is_empty(&self) -> bool {
+        self.api_version.is_none()
+            && self.added_methods.is_empty()
+            && self.removed_methods.is_empty()
+            && self.changed_methods.is_empty()
+    }
+}
+
+/// Compares `old` and `new` schemas.
+pub fn diff(old: &Schema, new: &Schema) -> SchemaDiff {
+    let version = |s: &Schema| format!("{} ({})", s.api_version.ver, s.api_version.date);
+    let api_version = Some((version(old), version(new))).filter(|(old, new)| old != new);
+
+    let find = |schema: &'_ Schema, name: &str| schema.methods.iter().any(|m| m.names.0 == name);
+
+    let added_methods = new
+        .methods
+        .iter()
+        .filter(|m| !find(old, &m.names.0))
+        .map(|m| m.names.0.clone())
+        .collect();
+
+    let removed_methods = old
+        .methods
+        .iter()
+        .filter(|m| !find(new, &m.names.0))
+        .map(|m| m.names.0.clone())
+        .collect();
+
+    let changed_methods = new
+        .methods
+        .iter()
+        .filter_map(|new| {
+            let old = old.methods.iter().find(|m| m.names.0 == new.names.0)?;
+            let changes = method_changes(old, new);
+
+            Some(MethodDiff {
+                method: new.names.0.clone(),
+                changes,
+            })
+            .filter(|diff| !diff.changes.is_empty())
+        })
+        .collect();
+
+    SchemaDiff {
+        api_version,
+        added_methods,
+        removed_methods,
+        changed_methods,
+    }
+}
+
+fn method_changes(old: &Method, new: &Method) -> Vec<Change> {
+    let mut changes = Vec::new();
+
+    if old.return_ty != new.return_ty {
+        changes.push(Change::ReturnType {
+            old: old.return_ty.to_string(),
+            new: new.return_ty.to_string(),
+        });
+    }
+
+    if old.doc != new.doc {
+        changes.push(Change::Doc {
+            old: doc_text(&old.doc),
+            new: doc_text(&new.doc),
+        });
+    }
+
+    if old.notes != new.notes {
+        let texts = |notes: &[Doc]| notes.iter().map(doc_text).collect();
+        changes.push(Change::Notes {
+            old: texts(&old.notes),
+            new: texts(&new.notes),
+        });
+    }
+
+    let find = |params: &'_ [Param], name: &str| params.iter().any(|p| p.name == name);
+
+    for p in old.params.iter().filter(|p| !find(&new.params, &p.name)) {
+        changes.push(Change::ParamRemoved {
+            param: p.name.clone(),
+            ty: p.ty.to_string(),
+        });
+    }
+
+    for p in new.params.iter().filter(|p| !find(&old.params, &p.name)) {
+        changes.push(Change::ParamAdded {
+            param: p.name.clone(),
+            ty: p.ty.to_string(),
+        });
+    }
+
+    for new in &new.params {
+        let old = match old.params.iter().find(|p| p.name == new.name) {
+            Some(old) => old,
+            None => continue,
+        };
+
+        match (unwrap_option(&old.ty), unwrap_option(&new.ty)) {
+            ((old_ty, old_opt), (new_ty, new_opt)) if old_ty == new_ty && old_opt != new_opt => {
+                changes.push(Change::ParamOptionality {
+                    param: new.name.clone(),
+                    optional: new_opt,
+                })
+            }
+            _ if old.ty != new.ty => changes.push(Change::ParamRetyped {
+                param: new.name.clone(),
+                old: old.ty.to_string(),
+                new: new.ty.to_string(),
+            }),
+            _ => {}
+        }
+
+        if old.descr != new.descr {
+            changes.push(Change::ParamDoc {
+                param: new.name.clone(),
+                old: doc_text(&old.descr),
+                new: doc_text(&new.descr),
+            });
+        }
+    }
+
+    changes
+}
+
+/// Text of `doc` with definitions of its links, so changes of links are
+/// visible in the diff too.
+fn doc_text(doc: &Doc) -> String {
+    doc.md.clone() + &doc.link_definitions()
+}
+
+/// Returns the type without `Option` and whether it was optional.
+fn unwrap_option(ty: &Type) -> (&Type, bool) {
+    match ty {
+        Type::Option(inner) => (inner, true),
+        ty => (ty, false),
+    }
+}
+
+impl fmt::Display for SchemaDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "no changes");
+        }
+
+        if let Some((old, new)) = &self.api_version {
+            writeln!(f, "api version: {} -> {}", old, new)?;
+        }
+
+        for m in &self.added_methods {
+            writeln!(f, "+ {}", m)?;
+        }
+
+        for m in &self.removed_methods {
+            writeln!(f, "- {}", m)?;
+        }
+
+        for m in &self.changed_methods {
+            writeln!(f, "~ {}", m.method)?;
+
+            for change in &m.changes {
+                write!(f, "{}", change)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        /// Old and new texts on separate lines, like in a unified diff.
+        fn texts(f: &mut fmt::Formatter<'_>, old: &str, new: &str) -> fmt::Result {
+            old.lines().try_for_each(|l| writeln!(f, "      - {}", l))?;
+            new.lines().try_for_each(|l| writeln!(f, "      + {}", l))
+        }
+
+        match self {
+            Change::ReturnType { old, new } => writeln!(f, "    return type: {} -> {}", old, new),
+            Change::Doc { old, new } => {
+                writeln!(f, "    doc:")?;
+                texts(f, old, new)
+            }
+            Change::Notes { old, new } => {
+                writeln!(f, "    notes:")?;
+                texts(f, &old.join("\n"), &new.join("\n"))
+            }
+            Change::ParamAdded { param, ty } => writeln!(f, "    + {}: {}", param, ty),
+            Change::ParamRemoved { param, ty } => writeln!(f, "    - {}: {}", param, ty),
+            Change::ParamRetyped { param, old, new } => {
+                writeln!(f, "    ~ {}: {} -> {}", param, old, new)
+            }
+            Change::ParamOptionality { param, optional } => {
+                let now = if *optional { "optional" } else { "required" };
+                writeln!(f, "    ~ {}: now {}", param, now)
+            }
+            Change::ParamDoc { param, old, new } => {
+                writeln!(f, "    ~ {} doc:", param)?;
+                texts(f, old, new)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::schema::{
+        test::{doc, method, opt},
+        Type,
+    };
+
+    use super::{method_changes, Change};
+
+    #[test]
+    fn no_changes() {
+        let m = method("do_thing", &[("a", Type::i64)]);
+
+        assert!(method_changes(&m, &m).is_empty());
+    }
+
+    #[test]
+    fn optionality() {
+        let required = method("do_thing", &[("a", Type::i64)]);
+        let optional = method("do_thing", &[("a", opt(Type::i64))]);
+
+        assert!(matches!(
+            &*method_changes(&required, &optional),
+            [Change::ParamOptionality { param, optional: true }] if param == "a"
+        ));
+        assert!(matches!(
+            &*method_changes(&optional, &required),
+            [Change::ParamOptionality { param, optional: false }] if param == "a"
+        ));
+    }
+
+    #[test]
+    fn retyped() {
+        let old = method("do_thing", &[("a", Type::i64), ("b", Type::String)]);
+        let new = method("do_thing", &[("a", Type::String), ("b", opt(Type::Url))]);
+
+        // Changing both the type and optionality is reported as a new type
+        assert!(matches!(
+            &*method_changes(&old, &new),
+            [
+                Change::ParamRetyped { param: a, old: a_old, new: a_new },
+                Change::ParamRetyped { param: b, old: b_old, new: b_new },
+            ] if a == "a" && a_old == "i64" && a_new == "String"
+                && b == "b" && b_old == "String" && b_new == "Option<Url>"
+        ));
+    }
+
+    #[test]
+    fn added_and_removed() {
+        let old = method("do_thing", &[("a", Type::i64)]);
+        let new = method("do_thing", &[("b", Type::i64)]);
+
+        assert!(matches!(
+            &*method_changes(&old, &new),
+            [Change::ParamRemoved { param: a, .. }, Change::ParamAdded { param: b, .. }]
+                if a == "a" && b == "b"
+        ));
+    }
+
+    #[test]
+    fn link_changes() {
+        let mut old = method("do_thing", &[("a", Type::i64)]);
+        old.doc = doc("See [x].");
+        old.doc
+            .md_links
+            .insert(String::from("x"), String::from("https://a"));
+
+        let mut new = method("do_thing", &[("a", Type::i64)]);
+        new.doc = doc("See [x].");
+        new.doc
+            .md_links
+            .insert(String::from("x"), String::from("https://b"));
+
+        assert!(matches!(
+            &*method_changes(&old, &new),
+            [Change::Doc { old, new }]
+                if old == "See [x].\n\n[x]: https://a" && new == "See [x].\n\n[x]: https://b"
+        ));
+    }
+}
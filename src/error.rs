@@ -0,0 +1,168 @@
+use std::{
+    fmt, io,
+    path::{Path, PathBuf},
+};
+
+use crate::{schema::Method, splice::SpliceError, validate::Problem};
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+#[derive(Debug)]
+pub enum Error {
+    /// Failed to read or write a file.
+    Io { path: PathBuf, source: io::Error },
+    /// Failed to parse a RON file.
+    Parse(Box<ParseError>),
+    /// Generated code doesn't parse as Rust.
+    InvalidCode(Box<InvalidCode>),
+    /// Failed to replace auto generated block in a file.
+    Splice { path: PathBuf, source: SpliceError },
+    /// Input is well-formed, but doesn't make sense (e.g. a patch refers to a
+    /// method that doesn't exist).
+    Semantic(String),
+    /// Error caused by a method of the schema.
+    InMethod { method: String, source: Box<Error> },
+    /// Schema validation found problems.
+    Validation(Vec<Problem>),
+    /// `--check` found files that are out of date.
+    Outdated(usize),
+}
+
+/// Details of [`Error::Parse`], boxed to keep `Result`s small.
+#[derive(Debug)]
+pub struct ParseError {
+    pub path: PathBuf,
+    /// Content of the file, used to show the erroneous line.
+    pub text: String,
+    pub source: ron::error::SpannedError,
+}
+
+/// Details of [`Error::InvalidCode`], boxed to keep `Result`s small.
+#[derive(Debug)]
+pub struct InvalidCode {
+    pub path: PathBuf,
+    /// Generated code, used to show the erroneous line.
+    pub text: String,
+    pub source: syn::Error,
+}
+
+impl Error {
+    pub fn io(path: impl Into<PathBuf>) -> impl FnOnce(io::Error) -> Self {
+        let path = path.into();
+        move |source| Error::Io { path, source }
+    }
+
+    pub fn semantic(msg: impl Into<String>) -> Self {
+        Error::Semantic(msg.into())
+    }
+
+    pub fn in_method(method: &Method) -> impl FnOnce(Self) -> Self + '_ {
+        move |source| Error::InMethod {
+            method: method.names.0.clone(),
+            source: Box::new(source),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io { path, source } => write!(f, "{}: {}", path.display(), source),
+            Error::Parse(err) => fmt::Display::fmt(err, f),
+            Error::InvalidCode(err) => fmt::Display::fmt(err, f),
+            Error::Splice { path, source } => write!(f, "{}: {}", path.display(), source),
+            Error::Semantic(msg) => f.write_str(msg),
+            Error::InMethod { method, source } => {
+                write!(
+                    f,
+                    "{}\n  = note: generated from method `{}`",
+                    source, method
+                )
+            }
+            Error::Validation(problems) => {
+                write!(f, "schema has {} problem(s):", problems.len())?;
+                for problem in problems {
+                    write!(f, "\n    {}", problem)?;
+                }
+
+                Ok(())
+            }
+            Error::Outdated(n) => write!(f, "{} file(s) are out of date", n),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io { source, .. } => Some(source),
+            Error::Parse(err) => Some(&err.source),
+            Error::InvalidCode(err) => Some(&err.source),
+            Error::InMethod { source, .. } => Some(source),
+            Error::Splice { source, .. } => Some(source),
+            Error::Semantic(_) | Error::Validation(_) | Error::Outdated(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ron::error::Position { line, col } = self.source.position;
+        write!(
+            f,
+            "failed to parse `{}`: {}",
+            self.path.display(),
+            self.source.code
+        )?;
+
+        // ron uses 1-based positions, `0:0` means "unknown"
+        if line == 0 {
+            return Ok(());
+        }
+
+        snippet(f, &self.path, &self.text, line, col)
+    }
+}
+
+impl fmt::Display for InvalidCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "generated `{}` is not valid Rust: {}",
+            self.path.display(),
+            self.source
+        )?;
+
+        // proc-macro2 uses 1-based lines and 0-based columns
+        let start = self.source.span().start();
+        snippet(f, &self.path, &self.text, start.line, start.column + 1)
+    }
+}
+
+/// Writes rustc-style snippet pointing at `line:col` (both 1-based) of `text`.
+fn snippet(
+    f: &mut fmt::Formatter<'_>,
+    path: &Path,
+    text: &str,
+    line: usize,
+    col: usize,
+) -> fmt::Result {
+    let snippet = text.lines().nth(line - 1).unwrap_or("");
+    let line_no = line.to_string();
+    let pad = " ".repeat(line_no.len());
+    let caret = " ".repeat(col.saturating_sub(1));
+
+    write!(
+        f,
+        "\n{pad}--> {path}:{line}:{col}\
+         \n{pad} |\
+         \n{line} | {snippet}\
+         \n{pad} | {caret}^",
+        pad = pad,
+        path = path.display(),
+        line = line_no,
+        col = col,
+        snippet = snippet,
+        caret = caret,
+    )
+}
@@ -1,9 +1,21 @@
-use std::{borrow::Borrow, collections::HashSet, ops::Deref};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::Path,
+};
 
 use itertools::Itertools;
 use kiam::when;
+use proc_macro2::TokenStream;
+use quote::quote;
 
-use crate::to_uppercase;
+use crate::{
+    error::{Error, Result},
+    keyword,
+    output::Output,
+    pretty,
+    signature::{ArgTy, Bound, RequesterSignature},
+    to_uppercase,
+};
 
 pub struct Payload {
     pub file_name: String,
@@ -11,116 +23,107 @@ pub struct Payload {
 }
 
 impl Payload {
-    pub fn generate(schema: &crate::schema::Schema) -> Vec<Self> {
+    pub fn generate(schema: &crate::schema::Schema) -> Result<Vec<Self>> {
         schema
             .methods
             .iter()
             .map(|method| {
                 let file_name = [method.names.2.as_str(), ".rs"].concat();
 
-                let uses = uses(&method);
-
-                let method_doc = render_doc(&method.doc, method.sibling.as_deref());
-                let eq_hash_derive = when! {
-                    eq_hash_suitable(&method) => " Eq, Hash,",
-                    _ => "",
-                };
-                let default_derive = when! {
-                    default_needed(&method) => " Default,",
-                    _ => "",
-                };
-
-                let return_ty = method.return_ty.to_string();
-
-                let required = params(
-                    method
-                        .params
-                        .iter()
-                        .filter(|p| !matches!(&p.ty, crate::schema::Type::Option(_))),
-                );
-                let required = when! {
-                    !required.is_empty() => format!("        required {{\n{}\n        }}", required),
-                    _ => required,
-                };
-
-                let optional = params(
-                    method
-                        .params
-                        .iter()
-                        .filter_map(|p| match &p.ty {
-                            crate::schema::Type::Option(inner) => {
-                                Some(crate::schema::Param {
-                                    name: p.name.clone(),
-                                    ty: inner.deref().clone(),
-                                    descr: p.descr.clone(),
-                                })
-                            },
-                            _ => None,
-                        })
-                );
-                let optional = when! {
-                    !optional.is_empty() => format!("\n        optional {{\n{}\n        }}", optional),
-                    _ => optional,
-                };
-
-
-                let multipart = multipart_input_file_fields(&method).map(|field| format!("    @[multipart = {}]\n", field.join(", "))).unwrap_or(String::new());
-
-                let derive = if !multipart.is_empty() || ["SendMediaGroup", "EditMessageMedia", "EditMessageMediaInline"].contains(&&*method.names.1) {
-                    format!("#[derive(Debug, Clone, Serialize)]")
-                } else {
-                    format!(
-                        "#[derive(Debug, PartialEq,{eq_hash_derive}{default_derive} Clone, Serialize)]",
-                        eq_hash_derive = eq_hash_derive,
-                        default_derive = default_derive
-                    )
-                };
-
-                let timeout_secs = when! {
-                    method.names.2 == "get_updates" => "    @[timeout_secs = timeout]\n",
-                    _ => ""
-                };
-
-                Payload {
+                let uses = uses(method).map_err(Error::in_method(method))?;
+                let payload = impl_payload(method).map_err(Error::in_method(method))?;
+
+                Ok(Payload {
                     file_name,
-                    content: format!(
-                        "\
-{uses}
-
-impl_payload! {{
-{multipart}{timeout_secs}{method_doc}
-    {derive}
-    pub {Method} ({Method}Setters) => {return_ty} {{
-{required}{optional}
-    }}
-}}
-",
-                        multipart = multipart,
-                        timeout_secs = timeout_secs,
-                        uses = uses,
-                        method_doc = method_doc,
-                        derive = derive,
-                        Method = method.names.1,
-                        return_ty = return_ty,
-                        required = required,
-                        optional = optional,
-                    ),
-                }
+                    content: format!("{}\n\n{}", uses, payload),
+                })
             })
             .collect()
     }
 }
 
-fn uses(method: &crate::schema::Method) -> String {
-    enum Use {
-        Prelude,
-        Crate(String),
-        External(String),
+/// `impl_payload!` invocation for a method.
+///
+/// The macro has custom syntax, so only attributes and types are formatted as
+/// Rust, the rest is laid out with [`pretty::Writer`].
+fn impl_payload(method: &crate::schema::Method) -> Result<String> {
+    let doc = method_doc(method)?;
+    let doc = pretty::doc(&render_doc(&doc, method.sibling.as_deref()));
+
+    let derive = when! {
+        is_multipart(method) => {
+            quote! { #[derive(Debug, Clone, Serialize)] }
+        },
+        _ => {
+            let eq_hash = when! {
+                eq_hash_suitable(method) => quote! { Eq, Hash, },
+                _ => quote! {},
+            };
+            let default = when! {
+                default_needed(method) => quote! { Default, },
+                _ => quote! {},
+            };
+
+            quote! { #[derive(Debug, PartialEq, #eq_hash #default Clone, Serialize)] }
+        },
+    };
+
+    let (optional, required): (Vec<_>, Vec<_>) = method
+        .params
+        .iter()
+        .partition(|p| matches!(p.ty, crate::schema::Type::Option(_)));
+
+    let mut w = pretty::Writer::default();
+    w.open("impl_payload! {");
+    if let Some(fields) = multipart_input_file_fields(method) {
+        w.line(&format!("@[multipart = {}]", fields.join(", ")));
+    }
+    if method.names.2 == "get_updates" {
+        w.line("@[timeout_secs = timeout]");
     }
+    w.line(&pretty::attrs(quote! { #doc #derive })?);
+    w.open(&format!(
+        "pub {name} ({name}Setters) => {return_ty} {{",
+        name = method.names.1,
+        return_ty = pretty::ty(&method.return_ty)?,
+    ));
+    params(&mut w, "required", &required)?;
+    params(&mut w, "optional", &optional)?;
+    w.close("}");
+    w.close("}");
+
+    Ok(w.finish())
+}
+
+/// Renders `use` items needed for a payload.
+fn uses(method: &crate::schema::Method) -> Result<String> {
+    let mut uses = Uses::default();
+    uses.external("serde", quote! { use serde::Serialize; });
+    uses.add_ty(&method.return_ty);
+    method.params.iter().for_each(|p| uses.add_ty(&p.ty));
+
+    uses.render(Vec::new())
+}
 
-    fn ty_use(ty: &crate::schema::Type) -> Use {
+/// `use` items needed for some types.
+#[derive(Default)]
+struct Uses {
+    /// `use` items for external crates, by crate name.
+    external: BTreeMap<&'static str, TokenStream>,
+    /// Names of types from `crate::types`.
+    types: BTreeSet<String>,
+}
+
+impl Uses {
+    fn external(&mut self, krate: &'static str, item: TokenStream) {
+        self.external.insert(krate, item);
+    }
+
+    fn add_ty(&mut self, ty: &crate::schema::Type) {
         match ty {
-            crate::schema::Type::True => Use::Crate(String::from("use crate::types::True;")),
+            crate::schema::Type::True => {
+                self.types.insert(String::from("True"));
+            }
             crate::schema::Type::u8
             | crate::schema::Type::u16
             | crate::schema::Type::u32
@@ -129,82 +132,144 @@ fn uses(method: &crate::schema::Method) -> String {
             | crate::schema::Type::i64
             | crate::schema::Type::f64
             | crate::schema::Type::bool
-            | crate::schema::Type::String => Use::Prelude,
+            | crate::schema::Type::String => {}
             crate::schema::Type::Option(inner) | crate::schema::Type::ArrayOf(inner) => {
-                ty_use(inner)
+                self.add_ty(inner)
             }
             crate::schema::Type::RawTy(raw) => {
-                Use::Crate(["use crate::types::", &raw, ";"].concat())
+                self.types.insert(raw.clone());
             }
-            crate::schema::Type::Url => Use::External(String::from("use url::Url;")),
+            crate::schema::Type::Url => self.external("url", quote! { use url::Url; }),
             crate::schema::Type::DateTime => {
-                Use::External(String::from("use chrono::{DateTime, Utc};"))
+                self.external("chrono", quote! { use chrono::{DateTime, Utc}; })
             }
         }
     }
 
-    let mut crate_uses = HashSet::new();
-    let mut external_uses = HashSet::new();
+    /// Adds types used in a `Requester` method signature.
+    fn add_signature(&mut self, sig: &RequesterSignature) {
+        for arg in &sig.args {
+            match &arg.ty {
+                ArgTy::Concrete(ty) => self.add_ty(ty),
+                ArgTy::Generic {
+                    bound: Bound::Into(ty),
+                    ..
+                }
+                | ArgTy::Generic {
+                    bound: Bound::IntoIterator(ty),
+                    ..
+                } => self.add_ty(ty),
+            }
+        }
+    }
 
-    external_uses.insert(String::from("use serde::Serialize;"));
+    /// Renders `use` items, `crate_paths` are additional paths imported from
+    /// `crate` (e.g. `requests::Request`).
+    ///
+    /// Output is deterministic: external crates go first, then `crate` imports,
+    /// each group sorted and separated by an empty line.
+    fn render(self, mut crate_paths: Vec<TokenStream>) -> Result<String> {
+        let external = pretty::file(self.external.into_values().collect())?;
 
-    core::iter::once(&method.return_ty)
-        .chain(method.params.iter().map(|p| &p.ty))
-        .map(ty_use)
-        .for_each(|u| match u {
-            Use::Prelude => {}
-            Use::Crate(u) => {
-                crate_uses.insert(u);
-            }
-            Use::External(u) => {
-                external_uses.insert(u);
-            }
+        let types = self
+            .types
+            .iter()
+            .map(|t| pretty::ident(t))
+            .collect::<Vec<_>>();
+        match &*types {
+            [] => {}
+            [single] => crate_paths.push(quote! { types::#single }),
+            many => crate_paths.push(quote! { types::{#(#many),*} }),
+        }
+
+        // Modules go before types, like rustfmt orders them
+        crate_paths.sort_by_key(|path| {
+            let path = path.to_string();
+            (path.starts_with(char::is_uppercase), path)
         });
 
-    let external_uses = external_uses.into_iter().join("\n");
+        let crate_uses = match &*crate_paths {
+            [] => return Ok(external.trim_end().to_owned()),
+            [single] => quote! { use crate::#single; },
+            many => quote! { use crate::{#(#many),*}; },
+        };
 
-    when! {
-        crate_uses.is_empty() => external_uses,
-        _ => {
-            let crate_uses = crate_uses
-                .into_iter()
-                .join("");
+        let crate_uses = pretty::file(crate_uses)?;
+        Ok(when! {
+            external.is_empty() => crate_uses.trim_end().to_owned(),
+            _ => format!("{}\n{}", external, crate_uses.trim_end()),
+        })
+    }
+}
 
-            format!("{external}\n\n{crate_}", external = external_uses, crate_ = crate_uses)
-        }
+/// Documentation of a method with a link to the official documentation and
+/// its notes appended as a "Notes" section.
+fn method_doc(method: &crate::schema::Method) -> Result<crate::schema::Doc> {
+    let mut doc = method.doc.clone();
+    doc.md += &format!("\n\n[The official docs]({}).", method.tg_doc);
+
+    if let Some(notes) = notes_section(&method.notes)? {
+        doc.md = [&doc.md, "\n\n", &notes.md].concat();
+        merge_links(&mut doc, &notes)?;
     }
+
+    Ok(doc)
 }
 
-fn render_doc(doc: &crate::schema::Doc, sibling: Option<&str>) -> String {
-    let links = when! {
-        doc.md_links.is_empty() => String::new(),
-        _ => {
-            let l = doc
-                .md_links
-                .iter()
-                .map(|(name, link)| format!("[{}]: {}", name, link))
-                .join("\n    /// ");
+/// Renders notes as a "Notes" section, returns `None` if there are no notes.
+fn notes_section(notes: &[crate::schema::Doc]) -> Result<Option<crate::schema::Doc>> {
+    if notes.is_empty() {
+        return Ok(None);
+    }
 
-            ["\n    ///\n    /// ", &l].concat()
-        }
+    let mut section = crate::schema::Doc {
+        md: String::from("## Notes"),
+        md_links: Default::default(),
     };
 
-    let sibling_note = sibling
-        .map(|s| {
-            format!(
-                "\n    /// \n    /// See also: [`{s}`](crate::payloads::{s})",
-                s = to_uppercase(s)
-            )
-        })
-        .unwrap_or_default();
+    for note in notes {
+        section.md += "\n- ";
+        section.md += &note.md.replace("\n", "\n  ");
+        merge_links(&mut section, note)?;
+    }
 
-    [
-        "    /// ",
-        &doc.md.replace("\n", "\n    /// "),
-        &sibling_note,
-        &links,
-    ]
-    .concat()
+    Ok(Some(section))
+}
+
+/// Adds links from `from` to `to`, it's an error if a link with the same name
+/// points to different places.
+fn merge_links(to: &mut crate::schema::Doc, from: &crate::schema::Doc) -> Result<()> {
+    for (name, link) in &from.md_links {
+        match to.md_links.get(name) {
+            Some(existing) if existing != link => {
+                return Err(Error::semantic(format!(
+                    "link `{}` is defined twice: `{}` and `{}`",
+                    name, existing, link
+                )))
+            }
+            Some(_) => {}
+            None => {
+                to.md_links.insert(name.clone(), link.clone());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Documentation text with a "See also" note for `sibling` and definitions of
+/// links appended.
+fn render_doc(doc: &crate::schema::Doc, sibling: Option<&str>) -> String {
+    let mut text = doc.md.clone();
+
+    if let Some(sibling) = sibling {
+        text += &format!(
+            "\n\nSee also: [`{s}`](crate::payloads::{s})",
+            s = to_uppercase(sibling)
+        );
+    }
+
+    text + &doc.link_definitions()
 }
 
 fn eq_hash_suitable(method: &crate::schema::Method) -> bool {
@@ -241,42 +306,51 @@ fn default_needed(method: &crate::schema::Method) -> bool {
         .all(|p| matches!(p.ty, crate::schema::Type::Option(_)))
 }
 
-fn params(params: impl Iterator<Item = impl Borrow<crate::schema::Param>>) -> String {
-    params
-        .map(|param| {
-            let param = param.borrow();
-            let doc = render_doc(&param.descr, None).replace("\n", "\n        ");
-            let field = &param.name;
-            let ty = &param.ty;
-            let flatten = match ty {
-                crate::schema::Type::RawTy(s) if s == "InputSticker" || s == "TargetMessage" => {
-                    "\n            #[serde(flatten)]"
-                }
-                _ => "",
-            };
-            let with = match ty {
-                crate::schema::Type::DateTime => {
-                    "\n            #[serde(with = \"crate::types::serde_opt_date_from_unix_timestamp\")]"
-                }
-                _ => "",
-            };
-            let rename = match field.strip_suffix('_') {
-                Some(field) => format!("\n            #[serde(rename = \"{}\")]", field),
-                None => "".to_owned(),
-            };
-            let convert = convert_for(ty);
-            format!(
-                "        {doc}{flatten}{with}{rename}\n            pub {field}: {ty}{convert},",
-                doc = doc,
-                flatten = flatten,
-                with = with,
-                rename = rename,
-                field = field,
-                ty = ty,
-                convert = convert
-            )
-        })
-        .join("\n")
+/// Adds a `required { ... }` or `optional { ... }` group of fields to an
+/// `impl_payload!` invocation, types of optional fields are unwrapped from
+/// `Option`.
+fn params(w: &mut pretty::Writer, group: &str, params: &[&crate::schema::Param]) -> Result<()> {
+    if params.is_empty() {
+        return Ok(());
+    }
+
+    w.open(&format!("{} {{", group));
+    for param in params {
+        let ty = match &param.ty {
+            crate::schema::Type::Option(inner) => inner,
+            ty => ty,
+        };
+
+        let doc = pretty::doc(&render_doc(&param.descr, None));
+        let flatten = match ty {
+            crate::schema::Type::RawTy(s) if s == "InputSticker" || s == "TargetMessage" => {
+                quote! { #[serde(flatten)] }
+            }
+            _ => quote! {},
+        };
+        let with = match ty {
+            crate::schema::Type::DateTime => {
+                quote! { #[serde(with = "crate::types::serde_opt_date_from_unix_timestamp")] }
+            }
+            _ => quote! {},
+        };
+        // serde strips `r#` from raw identifiers by itself
+        let rename = match keyword::unescape(&param.name) {
+            Some(name) if !param.name.starts_with("r#") => quote! { #[serde(rename = #name)] },
+            _ => quote! {},
+        };
+
+        w.line(&pretty::attrs(quote! { #doc #flatten #with #rename })?);
+        w.line(&format!(
+            "pub {}: {}{},",
+            param.name,
+            pretty::ty(ty)?,
+            convert_for(ty)
+        ));
+    }
+    w.close("}");
+
+    Ok(())
 }
 
 pub(crate) fn convert_for(ty: &crate::schema::Type) -> Convert {
@@ -320,6 +394,18 @@ impl std::fmt::Display for Convert {
     }
 }
 
+/// Whether `m` is sent as `multipart/form-data`: it has `InputFile` fields or
+/// may have them in `InputMedia`.
+fn is_multipart(m: &crate::schema::Method) -> bool {
+    multipart_input_file_fields(m).is_some()
+        || [
+            "SendMediaGroup",
+            "EditMessageMedia",
+            "EditMessageMediaInline",
+        ]
+        .contains(&&*m.names.1)
+}
+
 fn multipart_input_file_fields(m: &crate::schema::Method) -> Option<Vec<&str>> {
     let fields: Vec<_> = m
         .params
@@ -339,3 +425,1044 @@ fn ty_is_multiparty(ty: &crate::schema::Type) -> bool {
     matches!(ty, crate::schema::Type::RawTy(x) if x == "InputFile" || x == "InputSticker")
         || matches!(ty, crate::schema::Type::Option(inner) if ty_is_multiparty(inner))
 }
+
+/// Runs all generators.
+pub fn all(schema: &crate::schema::Schema) -> Result<Vec<Output>> {
+    let mut outputs = payloads(schema)?;
+    outputs.extend(payloads_mod(schema)?);
+    outputs.push(requester(schema)?);
+    outputs.push(requester_forward(schema)?);
+    outputs.push(requester_impls(schema)?);
+    outputs.push(bot(schema)?);
+    outputs.push(erased(schema)?);
+    outputs.push(api_version(schema)?);
+
+    Ok(outputs)
+}
+
+/// Generates `bot_api_version.rs` with constants describing the Bot API
+/// version the code was generated from.
+pub fn api_version(schema: &crate::schema::Schema) -> Result<Output> {
+    let crate::schema::ApiVersion { ver, date } = &schema.api_version;
+    let consts = vec![
+        quote! {
+            /// Version of the Telegram Bot API that payloads and the `Requester`
+            /// trait match, e.g. `"5.3"`.
+            pub const BOT_API_VERSION: &str = #ver;
+        },
+        quote! {
+            /// Release date of [`BOT_API_VERSION`], e.g. `"June 4, 2021"`.
+            pub const BOT_API_DATE: &str = #date;
+        },
+    ];
+
+    let content = format!(
+        "{header}\n{consts}",
+        header = crate::header("file", &schema.api_version),
+        consts = pretty::items(consts)?,
+    );
+
+    Ok(Output::new("bot_api_version.rs", content))
+}
+
+/// Generates one file per method, `payloads/{method}.rs`.
+pub fn payloads(schema: &crate::schema::Schema) -> Result<Vec<Output>> {
+    let header = crate::header("file", &schema.api_version);
+
+    Payload::generate(schema)?
+        .into_iter()
+        .map(|payload| {
+            let content = format!("{}\n{}", header, payload.content);
+
+            Ok(Output::new(
+                Path::new("payloads").join(&payload.file_name),
+                content,
+            ))
+        })
+        .collect()
+}
+
+/// Generates `payloads.rs` and `payloads/setters.rs`.
+pub fn payloads_mod(schema: &crate::schema::Schema) -> Result<Vec<Output>> {
+    let block = payloads_mod_block(schema)?;
+    let placeholder = pretty::placeholder("block");
+    let skeleton = pretty::items(vec![
+        quote! { #![doc = " Request data sent to Telegram."] },
+        quote! {
+            /// This module re-exports all the setters traits as `_`.
+            ///
+            /// When used with a glob import:
+            ///
+            /// ```
+            /// use teloxide_core::payloads::setters::*;
+            /// ```
+            ///
+            /// It allows you to use all the payloads setters, without polluting your
+            /// namespace.
+            pub mod setters;
+
+            #placeholder
+        },
+    ])?;
+    let block_header = crate::header("block", &schema.api_version);
+    let payloads = format!(
+        "{header}\n{code}",
+        header = crate::header("file", &schema.api_version),
+        code = splice_block(&skeleton, "", &block_header, &block),
+    );
+
+    let setters = by_category(schema)
+        .into_iter()
+        .map(|(descr, methods)| {
+            let setters = methods
+                .iter()
+                .map(|m| pretty::ident(&format!("{}Setters", m.names.1)));
+
+            Ok(format!(
+                "{}\n{}",
+                section_comment(descr, ""),
+                pretty::file(quote! {
+                    #[doc(no_inline)]
+                    pub use crate::payloads::{#(#setters as _),*};
+                })?,
+            ))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let setters = format!(
+        "{header}\n\n{setters}",
+        header = crate::header("file", &schema.api_version),
+        setters = setters.join("\n"),
+    );
+
+    Ok(vec![
+        Output::new("payloads.rs", payloads).with_block(block_header, block),
+        Output::new(Path::new("payloads").join("setters.rs"), setters),
+    ])
+}
+
+/// `mod` declarations and re-exports of all payloads, grouped by category.
+pub fn payloads_mod_block(schema: &crate::schema::Schema) -> Result<String> {
+    let sections = by_category(schema)
+        .into_iter()
+        .map(|(descr, methods)| {
+            let mods = methods.iter().map(|m| {
+                let m = pretty::ident(&m.names.2);
+                quote! { mod #m; }
+            });
+
+            let uses = methods.iter().map(|m| {
+                let module = pretty::ident(&m.names.2);
+                let payload = pretty::ident(&m.names.1);
+                let setters = pretty::ident(&format!("{}Setters", m.names.1));
+                quote! { pub use #module::{#payload, #setters}; }
+            });
+
+            Ok(format!(
+                "{}\n{}\n{}",
+                section_comment(descr, ""),
+                pretty::file(mods.collect())?,
+                pretty::file(uses.collect())?.trim_end()
+            ))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(sections.join("\n\n"))
+}
+
+/// Methods grouped by category, in the order of `tg_categoryes`, with
+/// category descriptions. Categories without methods are skipped.
+///
+/// Methods from categories that are not in `tg_categoryes` go to the last,
+/// "Other" category, so they are never lost.
+fn by_category(schema: &crate::schema::Schema) -> Vec<(&str, Vec<&crate::schema::Method>)> {
+    let other = schema
+        .methods
+        .iter()
+        .filter(|m| !schema.tg_categoryes.contains_key(&m.tg_category))
+        .collect::<Vec<_>>();
+
+    schema
+        .tg_categoryes
+        .iter()
+        .map(|(category, descr)| {
+            let methods = schema
+                .methods
+                .iter()
+                .filter(|m| m.tg_category == *category)
+                .collect::<Vec<_>>();
+
+            (&**descr, methods)
+        })
+        .chain(std::iter::once(("**Other**", other)))
+        .filter(|(_, methods)| !methods.is_empty())
+        .collect()
+}
+
+/// Generates `requester.rs` with the `Requester` trait.
+pub fn requester(schema: &crate::schema::Schema) -> Result<Output> {
+    let block = requester_block(schema)?;
+
+    let mut uses = Uses::default();
+    for m in &schema.methods {
+        uses.add_signature(&RequesterSignature::new(m));
+    }
+
+    let payloads = schema
+        .methods
+        .iter()
+        .map(|m| &m.names.1)
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .map(|name| pretty::ident(name));
+    let uses = uses.render(vec![
+        quote! { payloads::{#(#payloads),*} },
+        quote! { requests::Request },
+    ])?;
+
+    let doc = pretty::doc(&format!(
+        "\
+Methods for building requests.
+
+This trait is implemented by all bots and bot adaptors, methods correspond
+to methods of the [Telegram Bot API], grouped the same way as in its
+documentation.
+
+{categories}
+
+[Telegram Bot API]: https://core.telegram.org/bots/api",
+        categories = categories_doc(schema),
+    ));
+    let placeholder = pretty::placeholder("block");
+    let skeleton = pretty::file(quote! {
+        #doc
+        pub trait Requester {
+            /// Error type returned by all requests.
+            type Err: std::error::Error + Send;
+
+            #placeholder
+        }
+    })?;
+
+    let block_header = crate::header("block", &schema.api_version);
+    let content = format!(
+        "{header}\n{uses}\n\n{code}",
+        header = crate::header("file", &schema.api_version),
+        uses = uses,
+        code = splice_block(&skeleton, "", &block_header, &block),
+    );
+
+    Ok(Output::new("requester.rs", content).with_block(block_header, block))
+}
+
+/// "Methods by category" section of the `Requester` trait documentation, so
+/// the grouping is visible in rustdoc.
+fn categories_doc(schema: &crate::schema::Schema) -> String {
+    let mut doc = String::from("# Methods by category\n");
+
+    for (descr, methods) in by_category(schema) {
+        // The first line of a description is the category title
+        doc += &format!("\n- {}", descr.lines().next().unwrap_or_default());
+        for m in methods {
+            doc += &format!("\n  - [`{0}`](Requester::{0})", m.names.2);
+        }
+    }
+
+    doc
+}
+
+/// Associated types and methods of the `Requester` trait, grouped by
+/// categories.
+pub fn requester_block(schema: &crate::schema::Schema) -> Result<String> {
+    let sections = by_category(schema)
+        .into_iter()
+        .map(|(descr, methods)| {
+            let methods = methods
+                .iter()
+                .map(|m| requester_method(m).map_err(Error::in_method(m)))
+                .collect::<Result<Vec<_>>>()?;
+
+            Ok(format!(
+                "{}\n\n{}",
+                section_comment(descr, "    "),
+                methods.join("\n\n")
+            ))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(sections.join("\n\n"))
+}
+
+/// Associated type and method of the `Requester` trait for `m`.
+fn requester_method(m: &crate::schema::Method) -> Result<String> {
+    let sig = RequesterSignature::new(m);
+    let ty = pretty::ident(&m.names.1);
+    let fn_sig = sig.fn_sig();
+    let mut doc = format!(
+        "For Telegram documentation see [`{}`] and [the official docs]({}).",
+        m.names.1, m.tg_doc
+    );
+    if let Some(notes) = notes_section(&m.notes)? {
+        doc += "\n\n";
+        doc += &render_doc(&notes, None);
+    }
+    let doc = pretty::doc(&doc);
+
+    let items = vec![
+        quote! { type #ty: Request<Payload = #ty, Err = Self::Err>; },
+        quote! {
+            #doc
+            #fn_sig;
+        },
+    ];
+
+    pretty::nested(items, 1)
+}
+
+/// Renders description of a category as a comment.
+fn section_comment(descr: &str, indent: &str) -> String {
+    descr
+        .lines()
+        .map(|line| match line {
+            "" => format!("{}//", indent),
+            line => format!("{}// {}", indent, line),
+        })
+        .join("\n")
+}
+
+/// Generates `requester_forward.rs` with the `requester_forward!` macro.
+pub fn requester_forward(schema: &crate::schema::Schema) -> Result<Output> {
+    let block = requester_forward_block(schema)?;
+    let skeleton = pretty::file(pretty::placeholder("block"))?;
+
+    let block_header = crate::header("block", &schema.api_version);
+    let content = format!(
+        "{header}\n{code}",
+        header = crate::header("file", &schema.api_version),
+        code = splice_block(&skeleton, "", &block_header, &block),
+    );
+
+    Ok(Output::new("requester_forward.rs", content).with_block(block_header, block))
+}
+
+/// The `requester_forward!` macro.
+pub fn requester_forward_block(schema: &crate::schema::Schema) -> Result<String> {
+    let mut w = pretty::Writer::default();
+    w.open("macro_rules! requester_forward {")
+        .open("($i:ident $(, $rest:ident )* $(,)? => $body:ident, $ty:ident ) => {")
+        .line("requester_forward!(@method $i $body $ty);")
+        .open("$(")
+        .line("requester_forward!(@method $rest $body $ty);")
+        .close(")*")
+        .close("};");
+
+    for m in &schema.methods {
+        let sig = RequesterSignature::new(m);
+        let ty = pretty::ident(&m.names.1);
+        let name = pretty::ident(&m.names.2);
+        let fn_sig = sig.fn_sig();
+
+        w.blank().open(&format!(
+            "(@method {} $body:ident $ty:ident) => {{",
+            m.names.2
+        ));
+
+        // `$body!` is invoked with arguments in a custom syntax, so they are
+        // inserted after formatting
+        let body = pretty::nested(
+            vec![
+                quote! { type #ty = __cg_ty![#ty]; },
+                quote! {
+                    #fn_sig {
+                        let this = self;
+                        __cg_body!(#name this __cg_args)
+                    }
+                },
+            ],
+            w.depth(),
+        )
+        .map_err(Error::in_method(m))?
+        .replace("$args", &format!("({})", sig.args_text()));
+
+        w.indented(&body).close("};");
+    }
+    w.close("}");
+
+    Ok(w.finish())
+}
+
+/// Generates `bot/api.rs` with the `Requester` impl for `Bot`.
+pub fn bot(schema: &crate::schema::Schema) -> Result<Output> {
+    let block = bot_block(schema)?;
+
+    let mut uses = Uses::default();
+    let mut requests = BTreeSet::new();
+    for m in &schema.methods {
+        uses.add_signature(&RequesterSignature::new(m));
+        requests.insert(request_kind(m));
+    }
+
+    requests.insert("Requester");
+
+    let requests = requests.into_iter().map(pretty::ident).collect::<Vec<_>>();
+    let uses = uses.render(vec![
+        quote! { payloads },
+        quote! { requests::{#(#requests),*} },
+        quote! { Bot },
+    ])?;
+
+    let placeholder = pretty::placeholder("block");
+    let skeleton = pretty::file(quote! {
+        impl Requester for Bot {
+            type Err = crate::errors::RequestError;
+
+            #placeholder
+        }
+    })?;
+
+    let block_header = crate::header("block", &schema.api_version);
+    let content = format!(
+        "{header}\n{uses}\n\n{code}",
+        header = crate::header("file", &schema.api_version),
+        uses = uses,
+        code = splice_block(&skeleton, "", &block_header, &block),
+    );
+
+    Ok(Output::new(Path::new("bot").join("api.rs"), content).with_block(block_header, block))
+}
+
+/// Associated types and methods of the `Requester` impl for `Bot`.
+pub fn bot_block(schema: &crate::schema::Schema) -> Result<String> {
+    let methods = schema
+        .methods
+        .iter()
+        .map(|m| {
+            let sig = RequesterSignature::new(m);
+            let ty = pretty::ident(&m.names.1);
+            let kind = pretty::ident(request_kind(m));
+            let fn_sig = sig.fn_sig();
+            let args = sig.arg_idents();
+
+            let items = vec![
+                quote! { type #ty = #kind<payloads::#ty>; },
+                quote! {
+                    #fn_sig {
+                        Self::#ty::new(self.clone(), payloads::#ty::new(#(#args),*))
+                    }
+                },
+            ];
+
+            pretty::nested(items, 1).map_err(Error::in_method(m))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(methods.join("\n\n"))
+}
+
+/// Request type used by `Bot` to send `m`.
+fn request_kind(m: &crate::schema::Method) -> &'static str {
+    when! {
+        is_multipart(m) => "MultipartRequest",
+        _ => "JsonRequest",
+    }
+}
+
+/// Generates `adaptors/erased/requester.rs` with the object-safe
+/// `ErasableRequester` trait, its blanket impl and the `Requester` impl for
+/// `ErasedRequester`.
+///
+/// `ErasedRequest` and `ErasedRequester` (with an `inner` field holding
+/// `Arc<dyn ErasableRequester>`) are defined by hand in the parent module.
+pub fn erased(schema: &crate::schema::Schema) -> Result<Output> {
+    let mut uses = Uses::default();
+    let mut payloads = BTreeSet::new();
+    let mut impl_items = Vec::new();
+    let mut trait_items = Vec::new();
+    let mut blanket_items = Vec::new();
+
+    for m in &schema.methods {
+        let sig = RequesterSignature::new(m);
+        uses.add_signature(&sig);
+        payloads.insert(&m.names.1);
+
+        let name = pretty::ident(&m.names.2);
+        let ty = pretty::ident(&m.names.1);
+        let fn_sig = sig.fn_sig();
+
+        let params = sig.args.iter().map(|arg| {
+            let name = pretty::ident(&arg.param.name);
+            match &arg.ty {
+                ArgTy::Concrete(ty)
+                | ArgTy::Generic {
+                    bound: Bound::Into(ty),
+                    ..
+                } => {
+                    quote! { #name: #ty }
+                }
+                ArgTy::Generic {
+                    bound: Bound::IntoIterator(ty),
+                    ..
+                } => {
+                    quote! { #name: Vec<#ty> }
+                }
+            }
+        });
+        let erased_args = sig.args.iter().map(|arg| {
+            let name = pretty::ident(&arg.param.name);
+            match &arg.ty {
+                ArgTy::Concrete(_) => quote! { #name },
+                ArgTy::Generic {
+                    bound: Bound::Into(_),
+                    ..
+                } => quote! { #name.into() },
+                ArgTy::Generic {
+                    bound: Bound::IntoIterator(_),
+                    ..
+                } => {
+                    quote! { #name.into_iter().collect() }
+                }
+            }
+        });
+        let args = sig.arg_idents();
+        let erased_sig = quote! {
+            fn #name(&self, #(#params),*) -> ErasedRequest<'a, #ty, Self::Err>
+        };
+
+        impl_items.push(
+            pretty::nested(
+                vec![
+                    quote! { type #ty = ErasedRequest<'a, #ty, Self::Err>; },
+                    quote! {
+                        #fn_sig {
+                            self.inner.#name(#(#erased_args),*)
+                        }
+                    },
+                ],
+                1,
+            )
+            .map_err(Error::in_method(m))?,
+        );
+        trait_items
+            .push(pretty::nested(vec![quote! { #erased_sig; }], 1).map_err(Error::in_method(m))?);
+        blanket_items.push(
+            pretty::nested(
+                vec![quote! {
+                    #erased_sig {
+                        ErasedRequest::erase(Requester::#name(self, #(#args),*))
+                    }
+                }],
+                1,
+            )
+            .map_err(Error::in_method(m))?,
+        );
+    }
+
+    let payloads = payloads.into_iter().map(|p| pretty::ident(p));
+    let uses = uses.render(vec![
+        quote! { payloads::{#(#payloads),*} },
+        quote! { requests::Requester },
+    ])?;
+
+    let placeholders = ["impl_items", "trait_items", "blanket_items"].map(pretty::placeholder);
+    let [impl_placeholder, trait_placeholder, blanket_placeholder] = &placeholders;
+    let skeleton = pretty::items(vec![
+        quote! { use super::{ErasedRequest, ErasedRequester}; },
+        quote! {
+            impl<'a, E> Requester for ErasedRequester<'a, E>
+            where
+                E: std::error::Error + Send + 'a,
+            {
+                type Err = E;
+
+                #impl_placeholder
+            }
+        },
+        quote! {
+            /// Object-safe version of [`Requester`].
+            ///
+            /// Generic arguments are replaced with the types they are converted to,
+            /// requests are erased with [`ErasedRequest::erase`].
+            pub(super) trait ErasableRequester<'a> {
+                type Err: std::error::Error + Send;
+
+                #trait_placeholder
+            }
+        },
+        quote! {
+            impl<'a, B> ErasableRequester<'a> for B
+            where
+                B: Requester + 'a,
+            {
+                type Err = B::Err;
+
+                #blanket_placeholder
+            }
+        },
+    ])?;
+    let code = pretty::fill(&skeleton, |name, _| {
+        let items = match name {
+            "impl_items" => &impl_items,
+            "trait_items" => &trait_items,
+            _ => &blanket_items,
+        };
+        format!("\n{}\n", items.join("\n\n"))
+    });
+
+    let content = format!(
+        "{header}\n{uses}\n\n{code}",
+        header = crate::header("file", &schema.api_version),
+        uses = uses,
+        code = code,
+    );
+
+    Ok(Output::new(
+        Path::new("adaptors").join("erased").join("requester.rs"),
+        content,
+    ))
+}
+
+/// Generates `requester_impls.rs` with `Requester` impls for `&B`, `Box<B>`
+/// and `Arc<B>`.
+///
+/// Methods are forwarded with `requester_forward!`, `fwd_deref!` and `fty!`
+/// macros.
+pub fn requester_impls(schema: &crate::schema::Schema) -> Result<Output> {
+    let methods = schema
+        .methods
+        .iter()
+        .map(|m| &*m.names.2)
+        .collect::<Vec<_>>();
+    let forward = forward_methods(&methods, "fwd_deref", "fty")?;
+
+    let placeholder = pretty::placeholder("block");
+    let impls = vec![
+        quote! { &B },
+        quote! { Box<B> },
+        quote! { std::sync::Arc<B> },
+    ]
+    .into_iter()
+    .map(|ty| {
+        quote! {
+            impl<B> Requester for #ty
+            where
+                B: Requester,
+            {
+                type Err = B::Err;
+
+                #placeholder
+            }
+        }
+    });
+    let skeleton = pretty::items(impls)?;
+
+    let content = format!(
+        "{header}\n{uses}\n{impls}",
+        header = crate::header("file", &schema.api_version),
+        uses = pretty::file(quote! { use crate::requests::Requester; })?,
+        impls = pretty::fill(&skeleton, |_, _| format!("\n{}\n", forward)),
+    );
+
+    Ok(Output::new("requester_impls.rs", content))
+}
+
+/// Generates a `Requester` impl for an adaptor.
+///
+/// Methods from [`Adaptor::overrides`] are implemented by hand, all other
+/// methods are forwarded to the inner requester with `requester_forward!`,
+/// `fwd_inner!` and `fid!` macros. Adaptor files are
+/// [`splice_only`](Output::splice_only): only the forwarded methods are
+/// replaced in an existing file, `content` is a template for a new one.
+///
+/// [`Adaptor::overrides`]: crate::adaptor::Adaptor::overrides
+pub fn adaptor(
+    schema: &crate::schema::Schema,
+    adaptor: &crate::adaptor::Adaptor,
+) -> Result<Output> {
+    if let Some(unknown) = adaptor
+        .overrides
+        .iter()
+        .find(|o| !schema.methods.iter().any(|m| m.names.2 == **o))
+    {
+        return Err(Error::semantic(format!(
+            "adaptor `{}` overrides unknown method `{}`",
+            adaptor.name, unknown
+        )));
+    }
+
+    let (overridden, forwarded): (Vec<_>, Vec<_>) = schema
+        .methods
+        .iter()
+        .partition(|m| adaptor.overrides.contains(&m.names.2));
+
+    let block = forward_methods(
+        &forwarded.iter().map(|m| &*m.names.2).collect::<Vec<_>>(),
+        "fwd_inner",
+        "fid",
+    )?;
+
+    let name = pretty::ident(&adaptor.name);
+    let placeholder = pretty::placeholder("block");
+    let skeleton = pretty::items(vec![
+        quote! { use crate::requests::Requester; },
+        quote! {
+            impl<B> Requester for #name<B>
+            where
+                B: Requester,
+            {
+                type Err = B::Err;
+
+                #placeholder
+            }
+        },
+    ])?;
+
+    // Overridden methods are written by hand, so the template only lists them
+    let overridden = when! {
+        overridden.is_empty() => String::new(),
+        _ => format!(
+            "// Implemented by hand: {}",
+            overridden
+                .iter()
+                .map(|m| format!("`{}`", m.names.2))
+                .join(", "),
+        ),
+    };
+
+    let block_header = crate::header("block", &schema.api_version);
+    let content = splice_block(&skeleton, &overridden, &block_header, &block);
+
+    Ok(Output::new(&adaptor.path, content)
+        .with_block(block_header, block)
+        .splice_only())
+}
+
+/// `requester_forward! { a, b, ... => body, ty }` inside of an impl.
+fn forward_methods(methods: &[&str], body: &str, ty: &str) -> Result<String> {
+    if methods.is_empty() {
+        return Ok(String::new());
+    }
+
+    let methods = methods.iter().map(|m| pretty::ident(m));
+    let body = pretty::ident(body);
+    let ty = pretty::ident(ty);
+
+    pretty::nested(
+        vec![quote! { requester_forward! { #(#methods),* => #body, #ty } }],
+        1,
+    )
+}
+
+/// Fills the `block` placeholder of a skeleton with start and end markers
+/// (preceded by `comment`) and splices `block` between them.
+///
+/// This way a new file is the same as the one updated with `--splice` later.
+fn splice_block(skeleton: &str, comment: &str, header: &str, block: &str) -> String {
+    let template = pretty::fill(skeleton, |_, indent| {
+        let markers = [crate::splice::START_MARKER, crate::splice::END_MARKER];
+
+        let mut code = String::from("\n");
+        for line in comment.lines().chain(markers) {
+            code += &format!("{}{}\n", indent, line);
+        }
+        code
+    });
+
+    crate::splice::splice(&template, header, block)
+        .expect("the template has exactly one pair of markers")
+}
+
+#[cfg(test)]
+mod tests {
+    use itertools::Itertools;
+    use quote::quote;
+
+    use crate::{
+        adaptor::Adaptor,
+        schema::{
+            test::{method, opt, schema},
+            Schema, Type,
+        },
+    };
+
+    #[test]
+    fn adaptor_overriding_all_methods() {
+        let schema = schema(vec![
+            method("get_me", &[]),
+            method("send_dice", &[("chat_id", Type::i64)]),
+        ]);
+        let adaptor = Adaptor {
+            name: String::from("CacheMe"),
+            path: "cache_me.rs".into(),
+            overrides: vec![String::from("get_me"), String::from("send_dice")],
+        };
+
+        let output = super::adaptor(&schema, &adaptor).unwrap();
+
+        assert_eq!(output.block.unwrap().code, "");
+        assert!(output
+            .content
+            .contains("    // Implemented by hand: `get_me`, `send_dice`\n"));
+        assert!(!output.content.contains("requester_forward!"));
+    }
+
+    #[test]
+    fn multipart() {
+        let media = method(
+            "send_media_group",
+            &[(
+                "media",
+                Type::ArrayOf(Box::new(Type::RawTy(String::from("InputMedia")))),
+            )],
+        );
+        let photo = method(
+            "send_photo",
+            &[("photo", Type::RawTy(String::from("InputFile")))],
+        );
+        let json = method("get_me", &[]);
+
+        assert_eq!(super::request_kind(&media), "MultipartRequest");
+        assert_eq!(super::request_kind(&photo), "MultipartRequest");
+        assert_eq!(super::request_kind(&json), "JsonRequest");
+        assert!(super::impl_payload(&media)
+            .unwrap()
+            .contains("#[derive(Debug, Clone, Serialize)]"));
+    }
+
+    #[test]
+    fn blocks_can_be_spliced() {
+        let outputs = super::all(&schema(vec![method("get_me", &[])])).unwrap();
+
+        let mut spliced = 0;
+        for output in &outputs {
+            if let Some(block) = &output.block {
+                let content =
+                    crate::splice::splice(&output.content, &block.header, &block.code).unwrap();
+                assert_eq!(content, output.content, "{}", output.path.display());
+                spliced += 1;
+            }
+        }
+        assert_eq!(spliced, 4);
+    }
+
+    #[test]
+    fn uncategorized_methods() {
+        let mut sticker = method("get_sticker_set", &[]);
+        sticker.tg_category = String::from("stickers");
+        let schema = schema(vec![method("get_me", &[]), sticker]);
+
+        let categories = super::by_category(&schema)
+            .into_iter()
+            .map(|(descr, methods)| (descr, methods.len()))
+            .collect::<Vec<_>>();
+        assert_eq!(categories, [("**Available methods**", 1), ("**Other**", 1)]);
+
+        let requester = super::requester(&schema).unwrap().content;
+        assert!(requester.contains("    // **Other**\n"));
+        assert!(requester.contains("fn get_sticker_set(&self)"));
+    }
+
+    #[test]
+    fn keyword_params() {
+        let payload = |style| {
+            let schema = schema(vec![method("send_dice", &[("type", Type::String)])]);
+            let schema = crate::keyword::escape_params(schema, style);
+            super::impl_payload(&schema.methods[0]).unwrap()
+        };
+
+        let suffix = payload(crate::keyword::Escape::Suffix);
+        assert!(suffix.contains("#[serde(rename = \"type\")]\n"));
+        assert!(suffix.contains("pub type_: String"));
+
+        // serde strips `r#` by itself
+        let raw = payload(crate::keyword::Escape::Raw);
+        assert!(!raw.contains("rename"));
+        assert!(raw.contains("pub r#type: String"));
+    }
+
+    fn dice() -> Schema {
+        schema(vec![
+            method("get_me", &[]),
+            method(
+                "send_dice",
+                &[
+                    ("chat_id", Type::RawTy(String::from("ChatId"))),
+                    ("emoji", opt(Type::String)),
+                ],
+            ),
+        ])
+    }
+
+    #[test]
+    fn uses() {
+        let mut uses = super::Uses::default();
+        uses.add_ty(&opt(Type::Url));
+        uses.add_ty(&Type::RawTy(String::from("ChatId")));
+        uses.add_ty(&Type::u64);
+
+        assert_eq!(
+            uses.render(vec![quote! { requests::Request }]).unwrap(),
+            "use url::Url;\n\nuse crate::{requests::Request, types::ChatId};"
+        );
+        assert_eq!(super::Uses::default().render(Vec::new()).unwrap(), "");
+    }
+
+    #[test]
+    fn impl_payload() {
+        assert_eq!(
+            super::impl_payload(&dice().methods[1]).unwrap(),
+            r#"impl_payload! {
+    /// Does a thing.
+    ///
+    /// [The official docs](https://core.telegram.org/bots/api#senddice).
+    #[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize)]
+    pub SendDice (SendDiceSetters) => True {
+        required {
+            /// A param.
+            pub chat_id: ChatId [into],
+        }
+        optional {
+            /// A param.
+            pub emoji: String [into],
+        }
+    }
+}
+"#
+        );
+    }
+
+    #[test]
+    fn requester_method() {
+        assert_eq!(
+            super::requester_method(&dice().methods[1]).unwrap(),
+            r#"    type SendDice: Request<Payload = SendDice, Err = Self::Err>;
+
+    /// For Telegram documentation see [`SendDice`] and [the official docs](https://core.telegram.org/bots/api#senddice).
+    fn send_dice<C>(&self, chat_id: C) -> Self::SendDice
+    where
+        C: Into<ChatId>;"#
+        );
+    }
+
+    #[test]
+    fn bot_block() {
+        assert_eq!(
+            super::bot_block(&dice()).unwrap(),
+            r#"    type GetMe = JsonRequest<payloads::GetMe>;
+
+    fn get_me(&self) -> Self::GetMe {
+        Self::GetMe::new(self.clone(), payloads::GetMe::new())
+    }
+
+    type SendDice = JsonRequest<payloads::SendDice>;
+
+    fn send_dice<C>(&self, chat_id: C) -> Self::SendDice
+    where
+        C: Into<ChatId>,
+    {
+        Self::SendDice::new(self.clone(), payloads::SendDice::new(chat_id))
+    }"#
+        );
+    }
+
+    #[test]
+    fn erased() {
+        let content = super::erased(&dice()).unwrap().content;
+
+        assert!(content.contains(
+            r#"
+    type SendDice = ErasedRequest<'a, SendDice, Self::Err>;
+
+    fn send_dice<C>(&self, chat_id: C) -> Self::SendDice
+    where
+        C: Into<ChatId>,
+    {
+        self.inner.send_dice(chat_id.into())
+    }
+}
+"#
+        ));
+        assert!(content.contains(
+            r#"
+pub(super) trait ErasableRequester<'a> {
+    type Err: std::error::Error + Send;
+
+    fn get_me(&self) -> ErasedRequest<'a, GetMe, Self::Err>;
+
+    fn send_dice(&self, chat_id: ChatId) -> ErasedRequest<'a, SendDice, Self::Err>;
+}
+"#
+        ));
+        assert!(content.contains(
+            r#"
+    fn send_dice(&self, chat_id: ChatId) -> ErasedRequest<'a, SendDice, Self::Err> {
+        ErasedRequest::erase(Requester::send_dice(self, chat_id))
+    }
+"#
+        ));
+    }
+
+    #[test]
+    fn adaptor() {
+        let schema = dice();
+        let adaptor = Adaptor {
+            name: String::from("CacheMe"),
+            path: "cache_me.rs".into(),
+            overrides: vec![String::from("get_me")],
+        };
+        let output = super::adaptor(&schema, &adaptor).unwrap();
+
+        let block = output.block.unwrap();
+        assert_eq!(
+            block.code,
+            "    requester_forward! {\n        send_dice => fwd_inner, fid\n    }"
+        );
+        assert_eq!(
+            output.content,
+            format!(
+                r#"use crate::requests::Requester;
+
+impl<B> Requester for CacheMe<B>
+where
+    B: Requester,
+{{
+    type Err = B::Err;
+
+    // Implemented by hand: `get_me`
+    // START AUTO-GENERATED
+{header}
+{code}
+    // END AUTO-GENERATED
+}}
+"#,
+                header = block
+                    .header
+                    .lines()
+                    .map(|line| format!("    {}", line))
+                    .join("\n"),
+                code = block.code,
+            )
+        );
+    }
+
+    #[test]
+    fn api_version() {
+        let content = super::api_version(&dice()).unwrap().content;
+
+        assert!(content.ends_with(
+            r#"
+/// Version of the Telegram Bot API that payloads and the `Requester`
+/// trait match, e.g. `"5.3"`.
+pub const BOT_API_VERSION: &str = "5.3";
+
+/// Release date of [`BOT_API_VERSION`], e.g. `"June 4, 2021"`.
+pub const BOT_API_DATE: &str = "June 25, 2021";
+"#
+        ));
+    }
+
+    #[test]
+    fn no_methods() {
+        let outputs = super::all(&schema(Vec::new())).unwrap();
+
+        assert!(outputs.iter().any(|o| o.path.ends_with("requester.rs")));
+    }
+}
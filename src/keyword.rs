@@ -0,0 +1,111 @@
+//! Rust keywords and escaping of parameter names that are keywords.
+
+use crate::schema::Schema;
+
+/// Strict and reserved keywords of all editions (2015, 2018 and 2021).
+#[rustfmt::skip]
+pub const KEYWORDS: &[&str] = &[
+    // Strict
+    "as", "break", "const", "continue", "crate", "else", "enum", "extern", "false", "fn", "for",
+    "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return",
+    "self", "Self", "static", "struct", "super", "trait", "true", "type", "unsafe", "use", "where",
+    "while",
+    // Strict since 2018
+    "async", "await", "dyn",
+    // Reserved
+    "abstract", "become", "box", "do", "final", "macro", "override", "priv", "typeof", "unsized",
+    "virtual", "yield",
+    // Reserved since 2018
+    "try",
+];
+
+/// Keywords that can't be used as raw identifiers.
+const NOT_RAW: &[&str] = &["crate", "self", "Self", "super"];
+
+pub fn is_keyword(s: &str) -> bool {
+    KEYWORDS.contains(&s)
+}
+
+/// How to escape names that are keywords.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Escape {
+    /// `type` -> `type_`
+    Suffix,
+    /// `type` -> `r#type`, keywords that can't be raw identifiers (like
+    /// `self`) are escaped with a suffix.
+    Raw,
+}
+
+impl Escape {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "suffix" => Some(Escape::Suffix),
+            "raw" => Some(Escape::Raw),
+            _ => None,
+        }
+    }
+}
+
+/// Escapes `name` if it's a keyword.
+pub fn escape(name: &str, style: Escape) -> String {
+    match style {
+        _ if !is_keyword(name) => name.to_owned(),
+        Escape::Raw if !NOT_RAW.contains(&name) => format!("r#{}", name),
+        Escape::Raw | Escape::Suffix => format!("{}_", name),
+    }
+}
+
+/// Returns the original name if `name` was escaped with [`escape`].
+pub fn unescape(name: &str) -> Option<&str> {
+    name.strip_prefix("r#")
+        .or_else(|| name.strip_suffix('_'))
+        .filter(|name| is_keyword(name))
+}
+
+/// Escapes names of all parameters in the schema.
+pub fn escape_params(mut schema: Schema, style: Escape) -> Schema {
+    for param in schema.methods.iter_mut().flat_map(|m| &mut m.params) {
+        param.name = escape(&param.name, style);
+    }
+
+    schema
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{escape, unescape, Escape};
+
+    #[test]
+    fn suffix() {
+        assert_eq!(escape("type", Escape::Suffix), "type_");
+        assert_eq!(escape("self", Escape::Suffix), "self_");
+        assert_eq!(escape("chat_id", Escape::Suffix), "chat_id");
+
+        assert_eq!(unescape("type_"), Some("type"));
+        assert_eq!(unescape("self_"), Some("self"));
+    }
+
+    #[test]
+    fn raw() {
+        assert_eq!(escape("type", Escape::Raw), "r#type");
+        assert_eq!(escape("async", Escape::Raw), "r#async");
+        assert_eq!(escape("chat_id", Escape::Raw), "chat_id");
+
+        assert_eq!(unescape("r#type"), Some("type"));
+    }
+
+    #[test]
+    fn not_raw_fallback() {
+        // `r#self` and friends are not valid identifiers
+        for name in ["crate", "self", "Self", "super"] {
+            assert_eq!(escape(name, Escape::Raw), format!("{}_", name));
+        }
+    }
+
+    #[test]
+    fn not_escaped() {
+        assert_eq!(unescape("chat_id"), None);
+        assert_eq!(unescape("user_"), None);
+        assert_eq!(unescape("r#user"), None);
+    }
+}
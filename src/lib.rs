@@ -0,0 +1,79 @@
+//! Code generator for [teloxide-core], generates payloads and the `Requester`
+//! trait from the [schema] of the Telegram Bot API.
+//!
+//! Besides the `cg` binary, this can be used as a library, e.g. from a
+//! `build.rs`:
+//!
+//! 1. load the schema with [`Schema::load`](schema::Schema::load)
+//! 2. validate and patch it with [`prepare`]
+//! 3. generate code with functions from [`gen`] (they return in-memory
+//!    [`Output`](output::Output)s)
+//! 4. check the code with [`verify::verify`] and write it with
+//!    [`output::write`]
+//!
+//! [teloxide-core]: https://github.com/teloxide/teloxide-core
+//! [schema]: https://github.com/WaffleLapkin/tg-methods-schema
+
+pub mod adaptor;
+pub mod diff;
+pub mod error;
+pub mod gen;
+pub mod keyword;
+pub mod output;
+pub mod patch;
+mod pretty;
+pub mod schema;
+mod signature;
+pub mod splice;
+pub mod validate;
+pub mod verify;
+
+use crate::{
+    error::{Error, Result},
+    keyword::Escape,
+    patch::{Patches, Unused},
+    schema::Schema,
+};
+
+/// Validates `schema` and prepares it for generation: applies `patches` and
+/// escapes parameter names that are keywords.
+///
+/// Patches that didn't change anything are returned along with the schema.
+pub fn prepare(schema: Schema, patches: &Patches, escape: Escape) -> Result<(Schema, Vec<Unused>)> {
+    let problems = validate::validate(&schema);
+    if !problems.is_empty() {
+        return Err(Error::Validation(problems));
+    }
+
+    let mut unused = Vec::new();
+    let schema = patch::patch_sc(schema, patches, &mut unused)?;
+    let schema = patch::patch_ty(schema, patches, &mut unused)?;
+    // Patches refer to params by their original names, so this goes last
+    let schema = keyword::escape_params(schema, escape);
+
+    Ok((schema, unused))
+}
+
+fn to_uppercase(s: &str) -> String {
+    let mut chars = s.chars();
+    format!("{}{}", chars.next().unwrap().to_uppercase(), chars.as_str())
+}
+
+fn header(thing: &str, api: &schema::ApiVersion) -> String {
+    format! {
+    "\
+// This {lower} is auto generated by [`cg`] from [`schema`]
+// (Telegram Bot API {ver}, {date}).
+//
+// **DO NOT EDIT THIS {scream}**,
+//
+// Edit `cg` or `schema` instead.
+//
+// [cg]: https://github.com/teloxide/cg
+// [`schema`]: https://github.com/WaffleLapkin/tg-methods-schema",
+         lower = thing,
+         scream = thing.to_uppercase(),
+         ver = api.ver,
+         date = api.date,
+    }
+}
@@ -0,0 +1,201 @@
+use std::{
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use kiam::when;
+
+use crate::error::{Error, Result};
+
+/// A single generated file.
+pub struct Output {
+    /// Path of the file, relative to the output directory.
+    pub path: PathBuf,
+    pub content: String,
+    /// Generated part of the file, for files that are only partly generated.
+    ///
+    /// In [`Mode::Splice`] this is inserted between markers in an existing
+    /// file, instead of overwriting the whole file with `content`.
+    pub block: Option<Block>,
+    /// The file is hand-written and only `block` is generated.
+    ///
+    /// Existing files are always spliced, regardless of [`Mode`], `content`
+    /// is only used as a template when the file doesn't exist yet.
+    pub splice_only: bool,
+}
+
+/// Generated part of a partly generated file.
+pub struct Block {
+    /// Comment inserted right after the start marker.
+    pub header: String,
+    pub code: String,
+}
+
+impl Output {
+    pub fn new(path: impl Into<PathBuf>, content: String) -> Self {
+        Self {
+            path: path.into(),
+            content,
+            block: None,
+            splice_only: false,
+        }
+    }
+
+    pub fn with_block(self, header: String, code: String) -> Self {
+        Self {
+            block: Some(Block { header, code }),
+            ..self
+        }
+    }
+
+    /// Marks the output as [`splice_only`](Self::splice_only).
+    pub fn splice_only(self) -> Self {
+        Self {
+            splice_only: true,
+            ..self
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /// Overwrite files with generated content (except for
+    /// [`splice_only`](Output::splice_only) files, which are spliced).
+    Overwrite,
+    /// Replace only auto generated blocks in files that have them, see
+    /// [`crate::splice`].
+    Splice,
+}
+
+/// Writes all `outputs` into `dir`, creating missing directories.
+///
+/// Nothing is written if some of the files wouldn't be valid Rust.
+pub fn write(dir: &Path, outputs: &[Output], mode: Mode) -> Result<()> {
+    let rendered = outputs
+        .iter()
+        .map(|output| {
+            let path = dir.join(&output.path);
+            let content = render(&path, output, mode)?;
+            Ok((path, content))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    for (path, content) in rendered {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(Error::io(parent))?;
+        }
+
+        fs::write(&path, content).map_err(Error::io(&path))?;
+    }
+
+    Ok(())
+}
+
+/// Compares `outputs` with files in `dir` without writing anything.
+///
+/// Prints a unified diff for every file that is out of date and returns the
+/// number of such files.
+pub fn check(dir: &Path, outputs: &[Output], mode: Mode) -> Result<usize> {
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+    let mut outdated = 0;
+
+    for output in outputs {
+        let path = dir.join(&output.path);
+        let expected = render(&path, output, mode)?;
+        let actual = match fs::read_to_string(&path) {
+            Ok(actual) => actual,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(err) => return Err(Error::io(&path)(err)),
+        };
+
+        if actual != expected {
+            outdated += 1;
+
+            let name = output.path.display().to_string();
+            let diff = similar::TextDiff::from_lines(&actual, &expected);
+            write!(
+                stdout,
+                "{}",
+                diff.unified_diff()
+                    .header(&format!("a/{}", name), &format!("b/{}", name))
+            )
+            .map_err(Error::io("<stdout>"))?;
+        }
+    }
+
+    Ok(outdated)
+}
+
+/// Returns content that the file at `path` should have, checking that it's
+/// valid Rust.
+fn render(path: &Path, output: &Output, mode: Mode) -> Result<String> {
+    let splice = when! {
+        output.splice_only => path.exists(),
+        _ => mode == Mode::Splice,
+    };
+
+    let content = match &output.block {
+        Some(block) if splice => {
+            let existing = fs::read_to_string(path).map_err(Error::io(path))?;
+
+            crate::splice::splice(&existing, &block.header, &block.code).map_err(|source| {
+                Error::Splice {
+                    path: path.to_owned(),
+                    source,
+                }
+            })?
+        }
+        _ => output.content.clone(),
+    };
+
+    crate::verify::verify_text(path, &content)?;
+
+    Ok(content)
+}
+
+/// Prints all `outputs` to stdout.
+pub fn print(outputs: &[Output]) -> Result<()> {
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+
+    for output in outputs {
+        writeln!(stdout, "// {}", output.path.display())
+            .and_then(|()| writeln!(stdout, "{}", output.content))
+            .map_err(Error::io("<stdout>"))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::{
+        error::Error,
+        splice::{END_MARKER, START_MARKER},
+    };
+
+    use super::{write, Mode, Output};
+
+    #[test]
+    fn spliced_text_is_verified() {
+        let dir = std::env::temp_dir().join(format!("cg-output-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        // The hand-written part of the file is broken
+        let existing = format!("fn a( {{\n{}\n{}\n}}\n", START_MARKER, END_MARKER);
+        fs::write(dir.join("a.rs"), &existing).unwrap();
+
+        let outputs = [Output::new("a.rs", String::from("fn a() {}\n"))
+            .with_block(String::from("// Generated"), String::from("fn b() {}"))];
+        let res = write(&dir, &outputs, Mode::Splice);
+        let after = fs::read_to_string(dir.join("a.rs")).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(matches!(res, Err(Error::InvalidCode(_))));
+        assert_eq!(after, existing);
+    }
+}
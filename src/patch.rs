@@ -1,183 +1,169 @@
+use std::{convert::TryFrom, fmt, path::Path};
+
+use itertools::Itertools;
+
 use crate::{
+    error::{Error, Result},
     schema::{Schema, Type},
     to_uppercase,
 };
 
-pub fn patch_sc(mut schema: Schema) -> Schema {
-    fn check(l: &Option<&str>, r: &str) -> bool {
-        l.map(|m| r == m).unwrap_or(true)
+/// Patches loaded from a patch file (see `patches.ron` in the repository root).
+#[derive(Debug, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Patches {
+    /// Documentation patches.
+    #[serde(default)]
+    pub doc: Vec<DocPatch>,
+    /// Type patches.
+    #[serde(default)]
+    pub ty: Vec<TypePatch>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DocPatch {
+    pub target: Target,
+    pub patch: Patch,
+}
+
+/// Changes type of parameters from `from` to `to`.
+///
+/// It's an error if a parameter targeted exactly (i.e. without `*`) doesn't
+/// have `from` type. Targets with patterns only change parameters that have
+/// `from` type.
+#[derive(Debug, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TypePatch {
+    pub from: Type,
+    pub to: Type,
+    pub targets: Vec<ParamTarget>,
+}
+
+/// `method` and `param` are `snake_case` names, which may contain `*`
+/// wildcards, e.g. `(method: "*", param: "*_date")`.
+#[derive(Debug, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ParamTarget {
+    pub method: String,
+    pub param: String,
+}
+
+impl ParamTarget {
+    fn is_pattern(&self) -> bool {
+        self.method.contains('*') || self.param.contains('*')
     }
+}
 
-    schema.methods.iter_mut().for_each(|method| {
-        method
-            .params
-            .iter_mut()
-            .map(|p| &mut p.name)
-            .for_each(escape_kw);
+/// Patches bundled with cg, used when no patch file is given.
+static BUILTIN_PATCHES: &str = include_str!("../patches.ron");
+
+impl Patches {
+    pub fn load(path: &Path) -> Result<Self> {
+        crate::schema::load_ron(path)
+    }
+
+    pub fn builtin() -> Result<Self> {
+        crate::schema::parse_ron(Path::new("<builtin patches.ron>"), BUILTIN_PATCHES)
+    }
+}
+
+/// A patch that didn't change anything.
+#[derive(Debug)]
+pub struct Unused {
+    /// Where the patch is in the patch file, e.g. `doc[3]` or `ty[0].targets[1]`.
+    pub location: String,
+    pub description: String,
+}
+
+impl fmt::Display for Unused {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.location, self.description)
+    }
+}
+
+/// Applies documentation patches, patches that didn't change anything are
+/// added to `unused`.
+pub fn patch_sc(mut schema: Schema, patches: &Patches, unused: &mut Vec<Unused>) -> Result<Schema> {
+    fn check(l: &Option<String>, r: &str) -> bool {
+        l.as_ref().map(|m| r == m).unwrap_or(true)
+    }
+
+    let mut used = vec![false; patches.doc.len()];
 
-        DOC_PATCHES.iter().for_each(|(key, patch)| match key {
-            Target::Method(m) => {
-                if check(m, &method.names.0) {
-                    method.doc.patch(patch, *key);
+    for method in &mut schema.methods {
+        for (DocPatch { target, patch }, used) in patches.doc.iter().zip(&mut used) {
+            match target {
+                Target::Method(m) => {
+                    if check(m, &method.names.0) {
+                        *used |= method.doc.patch(patch, target)?;
+                    }
                 }
-            }
-            Target::Field {
-                method_name: m,
-                field_name: f,
-            } => {
-                if check(m, &method.names.0) {
-                    method
-                        .params
-                        .iter_mut()
-                        .filter(|p| check(f, &p.name))
-                        .for_each(|p| p.descr.patch(patch, *key))
+                Target::Notes(m) => {
+                    if check(m, &method.names.0) {
+                        for note in &mut method.notes {
+                            *used |= note.patch(patch, target)?;
+                        }
+                    }
                 }
-            }
-            Target::Any { method_name: m } => {
-                if check(m, &method.names.0) {
-                    method.doc.patch(patch, *key);
-
-                    method
-                        .params
-                        .iter_mut()
-                        .for_each(|p| p.descr.patch(patch, *key))
+                Target::Field {
+                    method_name: m,
+                    field_name: f,
+                } => {
+                    if check(m, &method.names.0) {
+                        for p in method.params.iter_mut().filter(|p| check(f, &p.name)) {
+                            *used |= p.descr.patch(patch, target)?;
+                        }
+                    }
+                }
+                Target::Any { method_name: m } => {
+                    if check(m, &method.names.0) {
+                        *used |= method.doc.patch(patch, target)?;
+
+                        for note in &mut method.notes {
+                            *used |= note.patch(patch, target)?;
+                        }
+
+                        for p in &mut method.params {
+                            *used |= p.descr.patch(patch, target)?;
+                        }
+                    }
                 }
             }
+        }
+    }
+
+    let doc_unused = patches
+        .doc
+        .iter()
+        .zip(used)
+        .enumerate()
+        .filter(|(_, (_, used))| !used)
+        .map(|(i, (patch, _))| Unused {
+            location: format!("doc[{}]", i),
+            description: format!("{:?} {}", patch.target, patch.patch),
         });
-    });
+    unused.extend(doc_unused);
 
-    schema
+    Ok(schema)
 }
 
-static DOC_PATCHES: &[(Target, Patch)] = &[
-    (
-        Target::Any { method_name: None },
-        Patch::ReplaceLink {
-            name: "More info on Sending Files »",
-            value: "crate::types::InputFile",
-        },
-    ),
-    (
-        Target::Field {
-            method_name: Some("sendChatAction"),
-            field_name: Some("action"),
-        },
-        Patch::ReplaceLink {
-            name: "text messages",
-            value: "crate::payloads::SendMessage",
-        },
-    ),
-    (
-        Target::Field {
-            method_name: Some("sendChatAction"),
-            field_name: Some("action"),
-        },
-        Patch::ReplaceLink {
-            name: "photos",
-            value: "crate::payloads::SendPhoto",
-        },
-    ),
-    (
-        Target::Field {
-            method_name: Some("sendChatAction"),
-            field_name: Some("action"),
-        },
-        Patch::ReplaceLink {
-            name: "videos",
-            value: "crate::payloads::SendVideo",
-        },
-    ),
-    (
-        Target::Field {
-            method_name: Some("sendChatAction"),
-            field_name: Some("action"),
-        },
-        Patch::ReplaceLink {
-            name: "audio files",
-            value: "crate::payloads::SendAudio",
-        },
-    ),
-    (
-        Target::Field {
-            method_name: Some("sendChatAction"),
-            field_name: Some("action"),
-        },
-        Patch::ReplaceLink {
-            name: "general files",
-            value: "crate::payloads::SendDocument",
-        },
-    ),
-    (
-        Target::Field {
-            method_name: Some("sendChatAction"),
-            field_name: Some("action"),
-        },
-        Patch::ReplaceLink {
-            name: "location data",
-            value: "crate::payloads::SendLocation",
-        },
-    ),
-    (
-        Target::Field {
-            method_name: Some("sendChatAction"),
-            field_name: Some("action"),
-        },
-        Patch::ReplaceLink {
-            name: "video notes",
-            value: "crate::payloads::SendVideoNote",
-        },
-    ),
-    (
-        Target::Field {
-            method_name: Some("sendChatAction"),
-            field_name: Some("action"),
-        },
-        Patch::ReplaceLink {
-            name: "stickers",
-            value: "crate::payloads::SendSticker",
-        },
-    ),
-    (
-        Target::Any { method_name: None },
-        Patch::Custom(intra_links),
-    ),
-    (
-        Target::Method(Some("addStickerToSet")),
-        Patch::Replace {
-            text: "You **must** use exactly one of the fields _png\\_sticker_ or _tgs\\_sticker_. ",
-            with: "",
-        },
-    ),
-    (
-        Target::Method(Some("GetFile")),
-        Patch::Replace {
-            text: "The file can then be downloaded via the link `https://api.telegram.org/file/bot<token>/<file_path>`, where `<file_path>` is taken from the response. It is guaranteed that the link will be valid for at least 1 hour. When the link expires, a new one can be requested by calling [`GetFile`] again.",
-            with: "The file can then be downloaded via the method [`Bot::download_file(file_path, dst)`], where `file_path` is taken from the response. It is guaranteed that the path from [`GetFile`] will be valid for at least 1 hour. When the path expires, a new one can be requested by calling [`GetFile`].",
-        },
-    ),
-    (
-        Target::Method(Some("GetFile")),
-        Patch::AddLink {
-            name: "`Bot::download_file(file_path, dst)`",
-            value: "crate::net::Download::download_file",
-        },
-    ),
-    // FIXME RETUNRS
-];
-
-#[derive(Debug, Clone, Copy)]
-enum Target<'a> {
+#[derive(Debug, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub enum Target {
     Any {
-        method_name: Option<&'a str>,
+        method_name: Option<String>,
     },
-    Method(Option<&'a str>),
+    Method(Option<String>),
+    /// All notes of a method.
+    Notes(Option<String>),
     Field {
-        method_name: Option<&'a str>,
-        field_name: Option<&'a str>,
+        method_name: Option<String>,
+        field_name: Option<String>,
     },
 }
 
-impl<'a> Target<'a> {
+impl Target {
     fn is_exact(&self) -> bool {
         match self {
             Target::Method(m) => m.is_some(),
@@ -185,45 +171,132 @@ impl<'a> Target<'a> {
                 method_name,
                 field_name,
             } => method_name.is_some() && field_name.is_some(),
-            Target::Any { method_name: _ } => false,
+            // A method may have several notes, a patch doesn't have to apply to
+            // all of them
+            Target::Notes(_) | Target::Any { method_name: _ } => false,
+        }
+    }
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub enum Patch {
+    ReplaceLink { name: String, value: String },
+    AddLink { name: String, value: String },
+    RemoveLink { name: String },
+    FullReplace { text: String, with: String },
+    Replace { text: String, with: String },
+    Custom(CustomPatch),
+}
+
+/// Function implementing a [`CustomPatch`].
+type CustomFn = fn(&mut crate::schema::Doc);
+
+/// Patch implemented in Rust, referred to by name in patch files.
+#[derive(serde::Deserialize)]
+#[serde(try_from = "String")]
+pub struct CustomPatch {
+    name: String,
+    f: CustomFn,
+}
+
+impl CustomPatch {
+    /// All known custom patches.
+    const ALL: &'static [(&'static str, CustomFn)] = &[("intra_links", intra_links)];
+}
+
+impl TryFrom<String> for CustomPatch {
+    type Error = String;
+
+    fn try_from(name: String) -> Result<Self, Self::Error> {
+        match Self::ALL.iter().find(|(n, _)| *n == name) {
+            Some(&(_, f)) => Ok(Self { name, f }),
+            None => Err(format!(
+                "unknown custom patch `{}`, expected one of {}",
+                name,
+                Self::ALL.iter().map(|(n, _)| format!("`{}`", n)).join(", ")
+            )),
         }
     }
 }
 
-enum Patch<'a> {
-    ReplaceLink { name: &'a str, value: &'a str },
-    AddLink { name: &'a str, value: &'a str },
-    RemoveLink { name: &'a str },
-    FullReplace { text: &'a str, with: &'a str },
-    Replace { text: &'a str, with: &'a str },
-    Custom(fn(&mut crate::schema::Doc)),
+impl fmt::Debug for CustomPatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.name)
+    }
+}
+
+impl fmt::Display for Patch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        /// Long texts make reports unreadable
+        fn short(s: &str) -> String {
+            const MAX: usize = 40;
+
+            match s.char_indices().nth(MAX) {
+                Some((i, _)) => format!("{:?}...", &s[..i]),
+                None => format!("{:?}", s),
+            }
+        }
+
+        match self {
+            Patch::ReplaceLink { name, .. } => write!(f, "ReplaceLink({})", short(name)),
+            Patch::AddLink { name, .. } => write!(f, "AddLink({})", short(name)),
+            Patch::RemoveLink { name } => write!(f, "RemoveLink({})", short(name)),
+            Patch::FullReplace { text, .. } => write!(f, "FullReplace({})", short(text)),
+            Patch::Replace { text, .. } => write!(f, "Replace({})", short(text)),
+            Patch::Custom(custom) => write!(f, "Custom({:?})", custom),
+        }
+    }
 }
 
 impl crate::schema::Doc {
-    fn patch(&mut self, patch: &Patch, key: Target) {
-        match patch {
+    /// Applies `patch`, returns `true` if the doc was changed.
+    fn patch(&mut self, patch: &Patch, key: &Target) -> Result<bool> {
+        let changed = match patch {
             Patch::ReplaceLink { name, value } => {
-                if let Some(link) = self.md_links.get_mut(*name) {
+                if let Some(link) = self.md_links.get_mut(name) {
+                    let changed = link != value;
                     link.clear();
-                    *link += *value;
+                    *link += value;
+                    changed
                 } else if key.is_exact() {
-                    panic!("Patch error: {:?} doesn't have link {}", key, name);
+                    return Err(Error::semantic(format!(
+                        "patch error: {:?} doesn't have link {}",
+                        key, name
+                    )));
+                } else {
+                    false
                 }
             }
             Patch::AddLink { name, value } => {
-                self.md_links
-                    .insert((*name).to_owned(), (*value).to_owned());
+                self.md_links.insert(name.clone(), value.clone()).as_ref() != Some(value)
             }
-            Patch::RemoveLink { name } => drop(self.md_links.remove(*name)),
+            Patch::RemoveLink { name } => self.md_links.remove(name).is_some(),
             Patch::FullReplace { text, with } => {
-                assert_eq!(self.md.as_str(), *text);
+                if self.md != *text {
+                    return Err(Error::semantic(format!(
+                        "patch error: {:?} expected doc {:?}, found {:?}",
+                        key, text, self.md
+                    )));
+                }
 
                 self.md.clear();
                 self.md += with;
+                text != with
             }
-            Patch::Replace { text, with } => self.md = self.md.replace(*text, with),
-            Patch::Custom(f) => f(self),
-        }
+            Patch::Replace { text, with } => {
+                let changed = !text.is_empty() && self.md.contains(&**text) && text != with;
+                self.md = self.md.replace(&**text, with);
+                changed
+            }
+            Patch::Custom(custom) => {
+                let before = self.clone();
+                (custom.f)(self);
+                *self != before
+            }
+        };
+
+        Ok(changed)
     }
 }
 
@@ -274,72 +347,203 @@ fn intra_links(doc: &mut crate::schema::Doc) {
     }
 }
 
-fn escape_kw(s: &mut String) {
-    if ["type"].contains(&s.as_str()) {
-        *s = format!("{}_", s);
+/// Applies type patches, pattern targets that didn't match anything are added
+/// to `unused`.
+pub fn patch_ty(mut schema: Schema, patches: &Patches, unused: &mut Vec<Unused>) -> Result<Schema> {
+    for (i, patch) in patches.ty.iter().enumerate() {
+        for (j, target) in patch.targets.iter().enumerate() {
+            if !patch_types(&mut schema, patch, target)? {
+                unused.push(Unused {
+                    location: format!("ty[{}].targets[{}]", i, j),
+                    description: format!(
+                        "{:?} -> {:?} for `{}::{}`",
+                        patch.from, patch.to, target.method, target.param
+                    ),
+                });
+            }
+        }
     }
+
+    Ok(schema)
 }
 
-pub(crate) fn patch_ty(mut schema: Schema) -> Schema {
-    // URLs
-    patch_types(
-        &mut schema,
-        Type::String,
-        Type::Url,
-        &[("set_webhook", "url")],
-    );
-
-    patch_types(
-        &mut schema,
-        Type::Option(Box::new(Type::String)),
-        Type::Option(Box::new(Type::Url)),
-        &[
-            ("answer_callback_query", "url"),
-            ("send_invoice", "photo_url"),
-        ],
-    );
-
-    // Dates
-    patch_types(
-        &mut schema,
-        Type::Option(Box::new(Type::u64)),
-        Type::Option(Box::new(Type::DateTime)),
-        &[
-            ("send_poll", "close_date"),
-            ("ban_chat_member", "until_date"),
-            ("kick_chat_member", "until_date"),
-            ("restrict_chat_member", "until_date"),
-        ],
-    );
-    patch_types(
-        &mut schema,
-        Type::Option(Box::new(Type::i64)),
-        Type::Option(Box::new(Type::DateTime)),
-        &[
-            ("create_chat_invite_link", "expire_date"),
-            ("edit_chat_invite_link", "expire_date"),
-        ],
-    );
+/// Returns `true` if any parameter was changed.
+fn patch_types(schema: &mut Schema, patch: &TypePatch, target: &ParamTarget) -> Result<bool> {
+    let TypePatch { from, to, .. } = patch;
 
-    schema
-}
+    if !target.is_pattern() {
+        let ParamTarget { method, param } = target;
 
-fn patch_types(schema: &mut Schema, from: Type, to: Type, list: &[(&str, &str)]) {
-    // URLs
-    for &(method, param) in list {
         let m = schema
             .methods
             .iter_mut()
-            .find(|m| m.names.2 == method)
-            .expect("Couldn't find method for pathcing");
+            .find(|m| m.names.2 == *method)
+            .ok_or_else(|| {
+                Error::semantic(format!("couldn't find method `{}` for patching", method))
+            })?;
 
         let p = m
             .params
             .iter_mut()
-            .find(|p| p.name == param)
-            .expect("Couldn't find parameter for pathcing");
+            .find(|p| p.name == *param)
+            .ok_or_else(|| {
+                Error::semantic(format!(
+                    "couldn't find parameter `{}::{}` for patching",
+                    method, param
+                ))
+            })?;
+
+        if p.ty != *from {
+            return Err(Error::semantic(format!(
+                "type patch error: expected `{}::{}` to have type {:?}, found {:?}",
+                method, param, from, p.ty
+            )));
+        }
 
-        assert_eq!(p.ty, from, "{}::{}", method, param);
         p.ty = to.clone();
+        return Ok(true);
+    }
+
+    // Patterns only change parameters that have the `from` type
+    let mut changed = false;
+    schema
+        .methods
+        .iter_mut()
+        .filter(|m| glob_match(&target.method, &m.names.2))
+        .flat_map(|m| m.params.iter_mut())
+        .filter(|p| glob_match(&target.param, &p.name) && p.ty == *from)
+        .for_each(|p| {
+            p.ty = to.clone();
+            changed = true;
+        });
+
+    Ok(changed)
+}
+
+/// Matches `s` against `pattern` where `*` matches any (possibly empty)
+/// sequence of characters.
+fn glob_match(pattern: &str, s: &str) -> bool {
+    match pattern.find('*') {
+        None => pattern == s,
+        Some(i) => {
+            let (prefix, rest) = (&pattern[..i], &pattern[i + 1..]);
+
+            s.starts_with(prefix) && {
+                let s = &s[prefix.len()..];
+                (0..=s.len())
+                    .filter(|&j| s.is_char_boundary(j))
+                    .any(|j| glob_match(rest, &s[j..]))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        error::Error,
+        schema::{
+            test::{method, opt},
+            Schema, Type,
+        },
+    };
+
+    use super::{glob_match, patch_types, ParamTarget, TypePatch};
+
+    fn schema() -> Schema {
+        crate::schema::test::schema(vec![
+            method("send_poll", &[("close_date", opt(Type::u64))]),
+            method(
+                "ban_chat_member",
+                &[("chat_id", Type::i64), ("until_date", opt(Type::u64))],
+            ),
+            method(
+                "create_chat_invite_link",
+                &[("expire_date", opt(Type::i64))],
+            ),
+        ])
+    }
+
+    fn patch(method: &str, param: &str) -> (TypePatch, ParamTarget) {
+        let target = ParamTarget {
+            method: method.to_owned(),
+            param: param.to_owned(),
+        };
+        let patch = TypePatch {
+            from: opt(Type::u64),
+            to: opt(Type::DateTime),
+            targets: Vec::new(),
+        };
+
+        (patch, target)
+    }
+
+    fn types(schema: &Schema) -> Vec<(&str, &Type)> {
+        schema
+            .methods
+            .iter()
+            .flat_map(|m| &m.params)
+            .map(|p| (&*p.name, &p.ty))
+            .collect()
+    }
+
+    #[test]
+    fn glob() {
+        assert!(glob_match("*", ""));
+        assert!(glob_match("*", "send_poll"));
+        assert!(glob_match("*_date", "until_date"));
+        assert!(!glob_match("*_date", "date"));
+        assert!(glob_match("*_chat_*", "ban_chat_member"));
+        assert!(glob_match("send_*_group", "send_media_group"));
+        assert!(!glob_match("send_*_group", "send_media"));
+        assert!(glob_match("send_poll", "send_poll"));
+        assert!(!glob_match("send_poll", "send_polls"));
+    }
+
+    #[test]
+    fn pattern_only_patches_matching_type() {
+        let mut schema = schema();
+        let (patch, target) = patch("*", "*_date");
+
+        assert!(patch_types(&mut schema, &patch, &target).unwrap());
+        assert_eq!(
+            types(&schema),
+            [
+                ("close_date", &opt(Type::DateTime)),
+                ("chat_id", &Type::i64),
+                ("until_date", &opt(Type::DateTime)),
+                // `i64` doesn't match `from`, so it's left untouched
+                ("expire_date", &opt(Type::i64)),
+            ]
+        );
+    }
+
+    #[test]
+    fn pattern_in_the_middle() {
+        let mut schema = schema();
+        let (patch, target) = patch("*_chat_*", "until_date");
+
+        assert!(patch_types(&mut schema, &patch, &target).unwrap());
+        assert_eq!(types(&schema)[0], ("close_date", &opt(Type::u64)));
+        assert_eq!(types(&schema)[2], ("until_date", &opt(Type::DateTime)));
+    }
+
+    #[test]
+    fn unmatched_pattern() {
+        let mut schema = schema();
+        let (patch, target) = patch("get_*", "*");
+
+        assert!(!patch_types(&mut schema, &patch, &target).unwrap());
+    }
+
+    #[test]
+    fn type_mismatch() {
+        let mut schema = schema();
+        let (patch, target) = patch("create_chat_invite_link", "expire_date");
+
+        assert!(matches!(
+            patch_types(&mut schema, &patch, &target),
+            Err(Error::Semantic(e)) if e.contains("type patch error")
+        ));
     }
 }
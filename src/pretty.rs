@@ -0,0 +1,261 @@
+//! Formatting of generated code.
+//!
+//! Code is built as a token tree (see [`quote`]), parsed with [`syn`] and
+//! printed with [`prettyplease`], so it's always syntactically valid and
+//! formatted the same way.
+//!
+//! Invocations of macros with custom syntax (like `impl_payload!`) can't be
+//! formatted like this, they are laid out with [`Writer`] from pieces that
+//! are formatted as Rust (attributes, types and items).
+//!
+//! `prettyplease` doesn't keep empty lines between items and can't print
+//! comments, so skeletons of files have [`placeholder`]s which are replaced
+//! with separately formatted items (or comments) by [`fill`].
+
+use itertools::Itertools;
+use kiam::when;
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::quote;
+
+use crate::error::{Error, Result};
+
+/// Formats `tokens` as a whole file.
+pub fn file(tokens: TokenStream) -> Result<String> {
+    let file = syn::parse2(tokens)
+        .map_err(|err| Error::semantic(format!("generated code is not valid Rust: {}", err)))?;
+
+    Ok(prettyplease::unparse(&file))
+}
+
+/// Formats top-level `items`, separated by empty lines.
+pub fn items(items: impl IntoIterator<Item = TokenStream>) -> Result<String> {
+    Ok(items
+        .into_iter()
+        .map(file)
+        .collect::<Result<Vec<_>>>()?
+        .join("\n"))
+}
+
+/// Formats `items` as if they were nested `depth` levels deep, e.g. inside of
+/// a trait (`depth = 1`) or a `macro_rules!` arm (`depth = 2`).
+///
+/// Items must be valid trait items, they are separated by empty lines. Empty
+/// items are skipped.
+/// Identifiers starting with `__cg_` are replaced with macro variables, e.g.
+/// `__cg_body` becomes `$body`.
+pub fn nested(items: impl IntoIterator<Item = TokenStream>, depth: usize) -> Result<String> {
+    assert!(depth > 0, "items must be nested at least once");
+
+    let items = items
+        .into_iter()
+        .map(|item| {
+            let mut tokens = quote! { trait __Cg { #item } };
+            for _ in 1..depth {
+                tokens = quote! { mod __cg { #tokens } };
+            }
+
+            Ok(unwrap(&file(tokens)?).replace("__cg_", "$"))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(items
+        .into_iter()
+        .filter(|item| !item.is_empty())
+        .join("\n\n"))
+}
+
+/// Returns the body of `trait __Cg { ... }` in formatted code, without the
+/// wrapper itself (and `mod __cg { ... }` around it).
+fn unwrap(text: &str) -> String {
+    const OPEN: &str = "trait __Cg {";
+
+    let start = text.find(OPEN).expect("items are wrapped in a trait");
+    let line_start = text[..start].rfind('\n').map_or(0, |i| i + 1);
+    let close = format!("{}}}", &text[line_start..start]);
+
+    // Without items the trait is printed as `trait __Cg {}`
+    match text[start + OPEN.len()..].strip_prefix('\n') {
+        Some(body) => body.lines().take_while(|line| *line != close).join("\n"),
+        None => String::new(),
+    }
+}
+
+/// Item that is replaced with other code by [`fill`] after formatting.
+pub fn placeholder(name: &str) -> TokenStream {
+    let name = ident(name);
+    quote! { __placeholder__!(#name); }
+}
+
+/// Replaces [`placeholder`]s in formatted code with code returned by `code`
+/// for the name and the indentation of the placeholder.
+pub fn fill(text: &str, mut code: impl FnMut(&str, &str) -> String) -> String {
+    text.split_inclusive('\n')
+        .map(|line| {
+            let trimmed = line.trim_start();
+            let indent = &line[..line.len() - trimmed.len()];
+
+            match trimmed
+                .trim_end()
+                .strip_prefix("__placeholder__!(")
+                .and_then(|rest| rest.strip_suffix(");"))
+            {
+                Some(name) => code(name, indent),
+                None => line.to_owned(),
+            }
+        })
+        .collect()
+}
+
+/// Formats attributes (e.g. `#[doc]` and `#[derive]`), one per line.
+pub fn attrs(attrs: TokenStream) -> Result<String> {
+    let text = file(quote! { #attrs struct __Cg; })?;
+
+    Ok(text.trim_end().trim_end_matches("struct __Cg;").to_owned())
+}
+
+/// Formats a type.
+pub fn ty(ty: impl quote::ToTokens) -> Result<String> {
+    let text = file(quote! { type __Cg = #ty; })?;
+
+    Ok(text
+        .trim_end()
+        .trim_start_matches("type __Cg = ")
+        .trim_end_matches(';')
+        .to_owned())
+}
+
+/// Lines of code indented by nesting, for macros with custom syntax.
+#[derive(Default)]
+pub struct Writer {
+    code: String,
+    depth: usize,
+}
+
+impl Writer {
+    /// Current nesting depth.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Adds `text` indented at the current depth, line by line.
+    pub fn line(&mut self, text: &str) -> &mut Self {
+        for line in text.lines() {
+            if !line.is_empty() {
+                self.code.push_str(&"    ".repeat(self.depth));
+            }
+            self.code.push_str(line);
+            self.code.push('\n');
+        }
+
+        self
+    }
+
+    /// Adds code that is already indented at the current depth (e.g. by
+    /// [`nested`]).
+    pub fn indented(&mut self, text: &str) -> &mut Self {
+        self.code.push_str(text);
+        self.code.push('\n');
+        self
+    }
+
+    /// Adds an empty line.
+    pub fn blank(&mut self) -> &mut Self {
+        self.code.push('\n');
+        self
+    }
+
+    /// Adds `open` (e.g. `mod a {`) and nests following lines.
+    pub fn open(&mut self, open: &str) -> &mut Self {
+        self.line(open);
+        self.depth += 1;
+        self
+    }
+
+    /// Ends nesting started by [`open`](Self::open) with `close` (e.g. `}`).
+    pub fn close(&mut self, close: &str) -> &mut Self {
+        self.depth -= 1;
+        self.line(close)
+    }
+
+    pub fn finish(self) -> String {
+        assert_eq!(self.depth, 0, "all nested blocks must be closed");
+        self.code
+    }
+}
+
+/// Makes an identifier, `r#name` is made into a raw identifier.
+pub fn ident(name: &str) -> Ident {
+    match name.strip_prefix("r#") {
+        Some(name) => Ident::new_raw(name, Span::call_site()),
+        None => Ident::new(name, Span::call_site()),
+    }
+}
+
+/// Makes `#[doc]` attributes, one per line of `text`.
+pub fn doc(text: &str) -> TokenStream {
+    text.lines()
+        .map(|line| {
+            // `///` comments have a space after slashes, but it's not a part of the line
+            let line = when! {
+                line.is_empty() => String::new(),
+                _ => format!(" {}", line),
+            };
+            quote! { #[doc = #line] }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use quote::quote;
+
+    use super::{attrs, file, fill, nested, placeholder, ty, Writer};
+
+    #[test]
+    fn nested_items() {
+        let items = vec![
+            quote! { fn a(&self, b: u8) -> u8; },
+            quote! {},
+            quote! { fn b(&self) { __cg_body!() } },
+        ];
+
+        assert_eq!(
+            nested(items.clone(), 1).unwrap(),
+            "    fn a(&self, b: u8) -> u8;\n\n    fn b(&self) {\n        $body!()\n    }"
+        );
+        assert_eq!(
+            nested(items, 2).unwrap(),
+            "        fn a(&self, b: u8) -> u8;\n\n        fn b(&self) {\n            $body!()\n        }"
+        );
+        assert_eq!(nested(Vec::new(), 1).unwrap(), "");
+    }
+
+    #[test]
+    fn fill_placeholders() {
+        let block = placeholder("block");
+        let text = file(quote! { impl A { type B = C; #block } }).unwrap();
+
+        assert_eq!(
+            fill(&text, |name, indent| format!("{}// {}\n", indent, name)),
+            "impl A {\n    type B = C;\n    // block\n}\n"
+        );
+    }
+
+    #[test]
+    fn writer() {
+        let mut w = Writer::default();
+        w.open("impl_payload! {")
+            .line(&attrs(quote! { #[doc = " Doc."] #[derive(Debug)] }).unwrap())
+            .open(&format!(
+                "pub A (ASetters) => {} {{",
+                ty(quote! { Vec<u8> }).unwrap()
+            ))
+            .close("}")
+            .close("}");
+
+        assert_eq!(
+            w.finish(),
+            "impl_payload! {\n    /// Doc.\n    #[derive(Debug)]\n    pub A (ASetters) => Vec<u8> {\n    }\n}\n"
+        );
+    }
+}
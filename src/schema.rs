@@ -1,4 +1,10 @@
+use std::path::Path;
+
 use indexmap::IndexMap as HashMap;
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+
+use crate::error::{Error, ParseError, Result};
 
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
 #[serde(deny_unknown_fields)]
@@ -30,7 +36,7 @@ pub struct Method {
     pub sibling: Option<String>,
 }
 
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct Doc {
     pub md: String,
@@ -69,16 +75,45 @@ pub enum Type {
 }
 
 impl Schema {
-    pub fn load(path: &str) -> Self {
-        use std::io::Read;
+    pub fn load(path: &Path) -> Result<Self> {
+        load_ron(path)
+    }
+}
+
+impl Doc {
+    /// Definitions of links (`[name]: link`, one per line) to be appended to
+    /// `md`, with an empty line before them. Empty if there are no links.
+    pub fn link_definitions(&self) -> String {
+        let mut text = String::new();
 
-        let mut file = std::fs::File::open(path).unwrap();
-        let mut str = String::new();
-        file.read_to_string(&mut str).unwrap();
-        ron::from_str::<Schema>(&str).unwrap()
+        if !self.md_links.is_empty() {
+            text += "\n";
+        }
+        for (name, link) in &self.md_links {
+            text += &format!("\n[{}]: {}", name, link);
+        }
+
+        text
     }
 }
 
+/// Reads and parses a RON file.
+pub(crate) fn load_ron<T: serde::de::DeserializeOwned>(path: &Path) -> Result<T> {
+    let text = std::fs::read_to_string(path).map_err(Error::io(path))?;
+    parse_ron(path, &text)
+}
+
+/// Parses RON, `path` is only used for error reporting.
+pub(crate) fn parse_ron<T: serde::de::DeserializeOwned>(path: &Path, text: &str) -> Result<T> {
+    ron::from_str(text).map_err(|source| {
+        Error::Parse(Box::new(ParseError {
+            path: path.to_owned(),
+            text: text.to_owned(),
+            source,
+        }))
+    })
+}
+
 impl std::fmt::Display for Type {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -100,3 +135,76 @@ impl std::fmt::Display for Type {
         }
     }
 }
+
+impl ToTokens for Type {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        tokens.extend(match self {
+            Type::Option(inner) => quote! { Option<#inner> },
+            Type::ArrayOf(inner) => quote! { Vec<#inner> },
+            Type::DateTime => quote! { DateTime<Utc> },
+            // All other types are a single identifier
+            ty => crate::pretty::ident(&ty.to_string()).into_token_stream(),
+        })
+    }
+}
+
+/// Builders of schema items for tests.
+#[cfg(test)]
+pub(crate) mod test {
+    use super::{ApiVersion, Doc, Method, Param, Schema, Type};
+
+    pub fn doc(md: &str) -> Doc {
+        Doc {
+            md: md.to_owned(),
+            md_links: Default::default(),
+        }
+    }
+
+    /// Method `name` (`snake_case`) from the `available-methods` category.
+    pub fn method(name: &str, params: &[(&str, Type)]) -> Method {
+        let pascal = name.split('_').map(crate::to_uppercase).collect::<String>();
+        let camel = name.chars().take(1).chain(pascal.chars().skip(1)).collect();
+
+        Method {
+            tg_doc: format!(
+                "https://core.telegram.org/bots/api#{}",
+                pascal.to_lowercase()
+            ),
+            names: (camel, pascal, name.to_owned()),
+            return_ty: Type::True,
+            doc: doc("Does a thing."),
+            tg_category: String::from("available-methods"),
+            notes: Vec::new(),
+            params: params
+                .iter()
+                .map(|(name, ty)| Param {
+                    name: (*name).to_owned(),
+                    ty: ty.clone(),
+                    descr: doc("A param."),
+                })
+                .collect(),
+            sibling: None,
+        }
+    }
+
+    /// Schema with `methods` and the `available-methods` category.
+    pub fn schema(methods: Vec<Method>) -> Schema {
+        Schema {
+            api_version: ApiVersion {
+                ver: String::from("5.3"),
+                date: String::from("June 25, 2021"),
+            },
+            methods,
+            tg_categoryes: vec![(
+                String::from("available-methods"),
+                String::from("**Available methods**"),
+            )]
+            .into_iter()
+            .collect(),
+        }
+    }
+
+    pub fn opt(ty: Type) -> Type {
+        Type::Option(Box::new(ty))
+    }
+}
@@ -0,0 +1,260 @@
+//! Signatures of `Requester` methods.
+//!
+//! All generators of `Requester`-related code render methods from
+//! [`RequesterSignature`], so that argument conversions and names of generic
+//! parameters are the same everywhere.
+
+use std::collections::HashSet;
+
+use indexmap::IndexMap;
+use itertools::Itertools;
+use kiam::when;
+use proc_macro2::{Ident, TokenStream};
+use quote::quote;
+
+use crate::{
+    gen::{convert_for, Convert},
+    keyword, pretty,
+    schema::{Method, Param, Type},
+    to_uppercase,
+};
+
+/// Signature of a single `Requester` method.
+pub struct RequesterSignature<'a> {
+    pub method: &'a Method,
+    /// Required parameters of the method, in the schema order.
+    pub args: Vec<Arg<'a>>,
+}
+
+/// A single argument of a `Requester` method.
+pub struct Arg<'a> {
+    pub param: &'a Param,
+    pub ty: ArgTy,
+}
+
+pub enum ArgTy {
+    /// The argument is passed as-is.
+    Concrete(Type),
+    /// The argument is generic, e.g. `T: Into<String>`.
+    Generic { name: String, bound: Bound },
+}
+
+pub enum Bound {
+    /// `Into<ty>`
+    Into(Type),
+    /// `IntoIterator<Item = ty>`
+    IntoIterator(Type),
+}
+
+impl<'a> RequesterSignature<'a> {
+    pub fn new(method: &'a Method) -> Self {
+        let required = method
+            .params
+            .iter()
+            .filter(|p| !matches!(p.ty, Type::Option(_)));
+
+        let mut names = generic_names(method);
+
+        let args = required
+            .map(|param| {
+                let bound = match convert_for(&param.ty) {
+                    Convert::Id(_) => None,
+                    Convert::Into(ty) => Some(Bound::Into(ty)),
+                    Convert::Collect(ty) => Some(Bound::IntoIterator(ty)),
+                };
+
+                let ty = match bound {
+                    None => ArgTy::Concrete(param.ty.clone()),
+                    Some(bound) => ArgTy::Generic {
+                        name: names.remove(&*param.name).expect("name of a generic"),
+                        bound,
+                    },
+                };
+
+                Arg { param, ty }
+            })
+            .collect();
+
+        Self { method, args }
+    }
+
+    /// `name: Type, ...` as a text.
+    ///
+    /// This is used in invocations of macros, which can't be formatted.
+    pub fn args_text(&self) -> String {
+        self.args
+            .iter()
+            .map(|arg| match &arg.ty {
+                ArgTy::Concrete(ty) => format!("{}: {}", arg.param.name, ty),
+                ArgTy::Generic { name, .. } => format!("{}: {}", arg.param.name, name),
+            })
+            .join(", ")
+    }
+
+    /// Names of the arguments.
+    pub fn arg_idents(&self) -> Vec<Ident> {
+        self.args
+            .iter()
+            .map(|arg| pretty::ident(&arg.param.name))
+            .collect()
+    }
+
+    /// `fn name<A, B>(&self, a: A, b: B) -> Self::Name where A: Into<..>, ...`
+    pub fn fn_sig(&self) -> TokenStream {
+        let name = pretty::ident(&self.method.names.2);
+        let ty = pretty::ident(&self.method.names.1);
+
+        let params = self.args.iter().map(|arg| {
+            let name = pretty::ident(&arg.param.name);
+            match &arg.ty {
+                ArgTy::Concrete(ty) => quote! { #name: #ty },
+                ArgTy::Generic { name: generic, .. } => {
+                    let generic = pretty::ident(generic);
+                    quote! { #name: #generic }
+                }
+            }
+        });
+
+        let generics = self
+            .generic_args()
+            .map(|(name, _)| pretty::ident(name))
+            .collect::<Vec<_>>();
+
+        let bounds = self.generic_args().map(|(name, bound)| {
+            let name = pretty::ident(name);
+            match bound {
+                Bound::Into(ty) => quote! { #name: Into<#ty> },
+                Bound::IntoIterator(ty) => quote! { #name: IntoIterator<Item = #ty> },
+            }
+        });
+
+        let generics = when! {
+            generics.is_empty() => quote! {},
+            _ => quote! { <#(#generics),*> },
+        };
+
+        quote! {
+            fn #name #generics (&self, #(#params),*) -> Self::#ty where #(#bounds),*
+        }
+    }
+
+    fn generic_args(&self) -> impl Iterator<Item = (&str, &Bound)> {
+        self.args.iter().filter_map(|arg| match &arg.ty {
+            ArgTy::Concrete(_) => None,
+            ArgTy::Generic { name, bound } => Some((&**name, bound)),
+        })
+    }
+}
+
+/// Names of generic parameters of impls that contain `Requester` methods
+/// (e.g. `impl<B> Requester for Throttle<B>`), methods can't reuse them.
+const RESERVED: &[&str] = &["B", "E"];
+
+/// Returns names of generic parameters for required params that need
+/// conversion, keyed by param name.
+///
+/// Every param gets the first of the following names that is not yet taken:
+/// the first letter (`chat_id` -> `C`), the initials (`chat_id` -> `CI`) and,
+/// finally, the first letter with a number (`C2`, `C3`, ...). Names only
+/// depend on the params before, so adding a param to the end of a method or
+/// adding an optional param doesn't rename existing generics.
+fn generic_names(m: &Method) -> IndexMap<&str, String> {
+    let mut taken: HashSet<String> = RESERVED.iter().map(|&name| name.to_owned()).collect();
+
+    m.params
+        .iter()
+        .filter(|p| !matches!(p.ty, Type::Option(_)))
+        .filter(|p| !matches!(convert_for(&p.ty), Convert::Id(_)))
+        .map(|p| {
+            let name = keyword::unescape(&p.name).unwrap_or(&p.name);
+            let words = name.split('_').filter(|w| !w.is_empty()).collect_vec();
+            let first = words
+                .first()
+                .map(|w| to_uppercase(&w[..1]))
+                .unwrap_or_else(|| String::from("T"));
+            let initials = words.iter().map(|w| to_uppercase(&w[..1])).join("");
+
+            let name = vec![first.clone(), initials]
+                .into_iter()
+                .chain((2..).map(|n| format!("{}{}", first, n)))
+                .find(|name| !taken.contains(name))
+                .expect("infinite iterator");
+
+            taken.insert(name.clone());
+            (&*p.name, name)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::schema::{test::method, Type};
+
+    use super::generic_names;
+
+    fn names(params: &[(&str, Type)]) -> Vec<String> {
+        generic_names(&method("do_thing", params))
+            .values()
+            .cloned()
+            .collect()
+    }
+
+    fn chat_id() -> Type {
+        Type::RawTy(String::from("ChatId"))
+    }
+
+    #[test]
+    fn initials() {
+        assert_eq!(
+            names(&[("user", Type::String), ("user_id", Type::String)]),
+            ["U", "UI"]
+        );
+    }
+
+    #[test]
+    fn same_prefix() {
+        assert_eq!(
+            names(&[
+                ("chat_id", chat_id()),
+                ("chat", Type::String),
+                ("c", Type::String),
+                ("chat_i", Type::String),
+            ]),
+            ["C", "C2", "C3", "CI"]
+        );
+    }
+
+    #[test]
+    fn reserved() {
+        assert_eq!(
+            names(&[("bio", Type::String), ("emoji", Type::String)]),
+            ["B2", "E2"]
+        );
+    }
+
+    #[test]
+    fn only_converted_required_params() {
+        assert_eq!(
+            names(&[
+                ("user_id", Type::i64),
+                ("text", Type::String),
+                ("title", Type::Option(Box::new(Type::String))),
+            ]),
+            ["T"]
+        );
+    }
+
+    #[test]
+    fn appending_params_keeps_names() {
+        let before = [("url", Type::String), ("text", Type::String)];
+        assert_eq!(names(&before), ["U", "T"]);
+
+        let mut required = before.to_vec();
+        required.push(("user", Type::String));
+        assert_eq!(names(&required), ["U", "T", "U2"]);
+
+        let mut optional = before.to_vec();
+        optional.push(("zzz", Type::Option(Box::new(Type::Url))));
+        assert_eq!(names(&optional), ["U", "T"]);
+    }
+}
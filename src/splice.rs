@@ -0,0 +1,181 @@
+//! Replacing auto generated blocks inside of hand-written files.
+//!
+//! A block is delimited by marker comments:
+//!
+//! ```text
+//! // START AUTO-GENERATED
+//! ...
+//! // END AUTO-GENERATED
+//! ```
+//!
+//! Everything between the markers is replaced, everything else (including the
+//! markers themselves) is preserved.
+
+use std::fmt;
+
+pub const START_MARKER: &str = "// START AUTO-GENERATED";
+pub const END_MARKER: &str = "// END AUTO-GENERATED";
+
+#[derive(Debug)]
+pub enum SpliceError {
+    /// Expected exactly one marker, found `found`.
+    MarkerCount { marker: &'static str, found: usize },
+    /// End marker goes before the start marker.
+    MarkerOrder,
+}
+
+impl fmt::Display for SpliceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SpliceError::MarkerCount { marker, found: 0 } => {
+                write!(f, "couldn't find `{}` marker", marker)
+            }
+            SpliceError::MarkerCount { marker, found } => write!(
+                f,
+                "expected exactly one `{}` marker, found {}",
+                marker, found
+            ),
+            SpliceError::MarkerOrder => write!(
+                f,
+                "`{}` marker goes before `{}` marker",
+                END_MARKER, START_MARKER
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SpliceError {}
+
+/// Replaces content between markers in `file` with `block`.
+///
+/// `block` is prefixed with `header`, indented the same way as the start
+/// marker.
+pub fn splice(file: &str, header: &str, block: &str) -> Result<String, SpliceError> {
+    let start = find_marker(file, START_MARKER)?;
+    let end = find_marker(file, END_MARKER)?;
+
+    if end.start < start.end {
+        return Err(SpliceError::MarkerOrder);
+    }
+
+    let indent = &file[start.start..][..start.indent];
+
+    let mut res = String::with_capacity(file.len() + block.len());
+    res.push_str(&file[..start.end]);
+    for line in header.lines() {
+        res.push_str(indent);
+        res.push_str(line);
+        res.push('\n');
+    }
+    res.push_str(block);
+    if !block.is_empty() && !block.ends_with('\n') {
+        res.push('\n');
+    }
+    res.push_str(&file[end.start..]);
+
+    Ok(res)
+}
+
+struct Marker {
+    /// Start of the marker line
+    start: usize,
+    /// End of the marker line (including `\n`)
+    end: usize,
+    /// Length of the whitespace before the marker
+    indent: usize,
+}
+
+fn find_marker(file: &str, marker: &'static str) -> Result<Marker, SpliceError> {
+    let mut found = Vec::new();
+    let mut offset = 0;
+
+    for line in file.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        if trimmed.trim_end() == marker {
+            found.push(Marker {
+                start: offset,
+                end: offset + line.len(),
+                indent: line.len() - trimmed.len(),
+            });
+        }
+
+        offset += line.len();
+    }
+
+    match found.len() {
+        1 => Ok(found.pop().unwrap()),
+        n => Err(SpliceError::MarkerCount { marker, found: n }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{splice, SpliceError, END_MARKER, START_MARKER};
+
+    const HEADER: &str = "// Generated\n// by cg";
+
+    fn file(inner: &str) -> String {
+        format!(
+            "trait T {{\n    fn a();\n\n    {}\n{}    {}\n}}\n",
+            START_MARKER, inner, END_MARKER
+        )
+    }
+
+    #[test]
+    fn replaces_block_and_indents_header() {
+        let res = splice(&file("    fn old();\n"), HEADER, "    fn new();").unwrap();
+
+        assert_eq!(res, file("    // Generated\n    // by cg\n    fn new();\n"));
+    }
+
+    #[test]
+    fn idempotent() {
+        let once = splice(&file(""), HEADER, "    fn new();\n").unwrap();
+        let twice = splice(&once, HEADER, "    fn new();\n").unwrap();
+
+        assert_eq!(once, twice);
+    }
+
+    #[test]
+    fn missing_marker() {
+        let file = format!("{}\n", START_MARKER);
+
+        assert!(matches!(
+            splice(&file, HEADER, ""),
+            Err(SpliceError::MarkerCount {
+                marker: END_MARKER,
+                found: 0
+            })
+        ));
+    }
+
+    #[test]
+    fn duplicate_marker() {
+        let file = format!("{0}\n{1}\n{0}\n{1}\n", START_MARKER, END_MARKER);
+
+        assert!(matches!(
+            splice(&file, HEADER, ""),
+            Err(SpliceError::MarkerCount {
+                marker: START_MARKER,
+                found: 2
+            })
+        ));
+    }
+
+    #[test]
+    fn reversed_markers() {
+        let file = format!("{}\n{}\n", END_MARKER, START_MARKER);
+
+        assert!(matches!(
+            splice(&file, HEADER, ""),
+            Err(SpliceError::MarkerOrder)
+        ));
+    }
+
+    #[test]
+    fn empty_block() {
+        let res = splice(&file("    fn old();\n"), HEADER, "").unwrap();
+
+        assert_eq!(res, file("    // Generated\n    // by cg\n"));
+    }
+}
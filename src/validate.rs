@@ -0,0 +1,194 @@
+//! Semantic checks of the schema, that are not covered by deserialization.
+
+use std::{collections::HashSet, fmt};
+
+use crate::{
+    keyword,
+    schema::{Doc, Method, Schema, Type},
+    to_uppercase,
+};
+
+/// A single problem found in the schema.
+#[derive(Debug)]
+pub struct Problem {
+    /// Where the problem is, e.g. `sendMessage.chat_id`.
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+/// Checks the schema, returning all found problems.
+pub fn validate(schema: &Schema) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    let mut report = |path: &str, message: String| {
+        problems.push(Problem {
+            path: path.to_owned(),
+            message,
+        })
+    };
+
+    let method_names: HashSet<_> = schema.methods.iter().map(|m| &*m.names.0).collect();
+    let mut seen = HashSet::new();
+
+    for method in &schema.methods {
+        let path = &*method.names.0;
+        let (camel, pascal, snake) = &method.names;
+
+        // `camel` and `snake` are the same for single-word methods (e.g. `close`)
+        let own: HashSet<_> = [camel, pascal, snake].iter().map(|n| &***n).collect();
+        for name in own {
+            if !seen.insert(name) {
+                report(path, format!("duplicate method name `{}`", name));
+            }
+        }
+
+        if *pascal != to_uppercase(camel) {
+            report(
+                path,
+                format!(
+                    "`{}` doesn't match `{}` (expected `{}`)",
+                    pascal,
+                    camel,
+                    to_uppercase(camel)
+                ),
+            );
+        }
+
+        if *snake != to_snake_case(camel) {
+            report(
+                path,
+                format!(
+                    "`{}` doesn't match `{}` (expected `{}`)",
+                    snake,
+                    camel,
+                    to_snake_case(camel)
+                ),
+            );
+        }
+
+        for name in [camel, pascal, snake].iter() {
+            if !is_ident(name) {
+                report(path, format!("`{}` is not a valid Rust identifier", name));
+            }
+        }
+
+        // e.g. `https://core.telegram.org/bots/api#sendmessage`
+        match method.tg_doc.rsplit_once('#') {
+            Some((_, anchor)) if anchor == camel.to_lowercase() => {}
+            _ => report(
+                &format!("{}.tg_doc", path),
+                format!(
+                    "`{}` doesn't link to `{}` (expected anchor `#{}`)",
+                    method.tg_doc,
+                    camel,
+                    camel.to_lowercase()
+                ),
+            ),
+        }
+
+        if let Some(sibling) = &method.sibling {
+            if !method_names.contains(&**sibling) {
+                report(path, format!("sibling `{}` doesn't exist", sibling));
+            }
+        }
+
+        if !schema.tg_categoryes.contains_key(&method.tg_category) {
+            report(
+                path,
+                format!(
+                    "category `{}` is not in `tg_categoryes`",
+                    method.tg_category
+                ),
+            );
+        }
+
+        check_ty(
+            &method.return_ty,
+            &format!("{}.return_ty", path),
+            &mut report,
+        );
+        check_doc(&method.doc, path, &mut report);
+        for (i, note) in method.notes.iter().enumerate() {
+            check_doc(note, &format!("{}.notes[{}]", path, i), &mut report);
+        }
+
+        check_params(method, &mut report);
+    }
+
+    problems
+}
+
+fn check_params(method: &Method, report: &mut impl FnMut(&str, String)) {
+    let mut seen = HashSet::new();
+
+    for param in &method.params {
+        let path = format!("{}.{}", method.names.0, param.name);
+
+        if !seen.insert(&*param.name) {
+            report(&path, String::from("duplicate parameter"));
+        }
+
+        // Keywords are fine here, they are escaped by `keyword::escape_params`
+        if !is_ident_or_keyword(&param.name) {
+            report(&path, String::from("name is not a valid Rust identifier"));
+        }
+
+        check_ty(&param.ty, &path, report);
+        check_doc(&param.descr, &path, report);
+    }
+}
+
+fn check_ty(ty: &Type, path: &str, report: &mut impl FnMut(&str, String)) {
+    match ty {
+        Type::Option(inner) if matches!(**inner, Type::Option(_)) => {
+            report(path, format!("nested option `{:?}`", ty))
+        }
+        Type::Option(inner) | Type::ArrayOf(inner) => check_ty(inner, path, report),
+        Type::RawTy(raw) if !is_ident(raw) => {
+            report(path, format!("`{}` is not a valid Rust type name", raw))
+        }
+        _ => {}
+    }
+}
+
+fn check_doc(doc: &Doc, path: &str, report: &mut impl FnMut(&str, String)) {
+    if doc.md.trim().is_empty() {
+        report(path, String::from("empty documentation"));
+    }
+}
+
+/// Converts `camelCase` to `snake_case`.
+fn to_snake_case(s: &str) -> String {
+    let mut res = String::with_capacity(s.len() + 4);
+    for c in s.chars() {
+        if c.is_uppercase() {
+            res.push('_');
+            res.extend(c.to_lowercase());
+        } else {
+            res.push(c);
+        }
+    }
+
+    res
+}
+
+/// Returns `true` if `s` can be used as an identifier (and a file name).
+fn is_ident(s: &str) -> bool {
+    is_ident_or_keyword(s) && !keyword::is_keyword(s)
+}
+
+fn is_ident_or_keyword(s: &str) -> bool {
+    let mut chars = s.chars();
+
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => {}
+        _ => return false,
+    }
+
+    s != "_" && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
@@ -0,0 +1,237 @@
+//! Checking that generated code is valid Rust.
+//!
+//! Besides parsing whole files, this also parses bodies of the macros we
+//! generate (`impl_payload!` and `requester_forward!`), since Rust parsers
+//! don't look inside of macro invocations.
+
+use std::path::Path;
+
+use proc_macro2::{TokenStream, TokenTree};
+use quote::{format_ident, quote};
+use syn::{
+    braced, bracketed, parenthesized,
+    parse::{Parse, ParseStream},
+    punctuated::Punctuated,
+    token, Attribute, Ident, Item, Token,
+};
+
+use crate::{
+    error::{Error, InvalidCode, Result},
+    output::Output,
+    schema::{Method, Schema},
+};
+
+/// Checks that all `outputs` parse as Rust.
+pub fn verify(schema: &Schema, outputs: &[Output]) -> Result<()> {
+    for output in outputs {
+        if let Err(source) = verify_file(&output.content) {
+            let line = source.span().start().line;
+            let method = method_at(schema, &output.path, &output.content, line);
+            let err = Error::InvalidCode(Box::new(InvalidCode {
+                path: output.path.clone(),
+                text: output.content.clone(),
+                source,
+            }));
+
+            return Err(match method {
+                Some(method) => Error::in_method(method)(err),
+                None => err,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks that `text` that is about to be written to `path` parses as Rust.
+///
+/// Unlike [`verify`], this checks the final text of the file, e.g. a
+/// hand-written file with a generated block spliced into it.
+pub fn verify_text(path: &Path, text: &str) -> Result<()> {
+    verify_file(text).map_err(|source| {
+        Error::InvalidCode(Box::new(InvalidCode {
+            path: path.to_owned(),
+            text: text.to_owned(),
+            source,
+        }))
+    })
+}
+
+fn verify_file(text: &str) -> syn::Result<()> {
+    let file = syn::parse_file(text)?;
+
+    for item in file.items {
+        match item {
+            Item::Macro(item) if item.mac.path.is_ident("impl_payload") => {
+                item.mac.parse_body::<PayloadMacro>()?;
+            }
+            Item::Macro(item) if item.mac.path.is_ident("macro_rules") => {
+                item.mac.parse_body::<MacroRules>()?;
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the method that generated code at `line`.
+///
+/// Payload files are generated from a single method, for other files this
+/// looks for the closest line above `line` that mentions a method.
+fn method_at<'s>(schema: &'s Schema, path: &Path, text: &str, line: usize) -> Option<&'s Method> {
+    let from_file = path
+        .parent()
+        .filter(|dir| *dir == Path::new("payloads"))
+        .and(path.file_stem())
+        .and_then(|stem| schema.methods.iter().find(|m| *stem == *m.names.2));
+
+    let mentions = |line: &str| {
+        line.split(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+            .find_map(|word| {
+                let word = word.strip_suffix("Setters").unwrap_or(word);
+                schema
+                    .methods
+                    .iter()
+                    .find(|m| word == m.names.1 || word == m.names.2)
+            })
+    };
+
+    from_file.or_else(|| {
+        let lines = text.lines().take(line).collect::<Vec<_>>();
+        lines.into_iter().rev().find_map(mentions)
+    })
+}
+
+/// Body of `impl_payload!`.
+///
+/// ```text
+/// @[multipart = photo]
+/// /// Docs
+/// #[derive(...)]
+/// pub SendPhoto (SendPhotoSetters) => Message {
+///     required {
+///         /// Docs
+///         pub chat_id: ChatId [into],
+///     }
+///     optional { ... }
+/// }
+/// ```
+struct PayloadMacro;
+
+impl Parse for PayloadMacro {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        while input.peek(Token![@]) {
+            input.parse::<Token![@]>()?;
+
+            let content;
+            bracketed!(content in input);
+            content.parse::<Ident>()?;
+            content.parse::<Token![=]>()?;
+            Punctuated::<Ident, Token![,]>::parse_separated_nonempty(&content)?;
+        }
+
+        input.call(Attribute::parse_outer)?;
+        input.parse::<Token![pub]>()?;
+        input.parse::<Ident>()?;
+
+        let setters;
+        parenthesized!(setters in input);
+        setters.parse::<Ident>()?;
+
+        input.parse::<Token![=>]>()?;
+        input.parse::<syn::Type>()?;
+
+        let body;
+        braced!(body in input);
+        while !body.is_empty() {
+            let kind = body.parse::<Ident>()?;
+            if kind != "required" && kind != "optional" {
+                return Err(syn::Error::new(
+                    kind.span(),
+                    "expected `required` or `optional`",
+                ));
+            }
+
+            let fields;
+            braced!(fields in body);
+            while !fields.is_empty() {
+                fields.call(Attribute::parse_outer)?;
+                fields.parse::<Token![pub]>()?;
+                fields.parse::<Ident>()?;
+                fields.parse::<Token![:]>()?;
+                fields.parse::<syn::Type>()?;
+
+                // `[into]`, `[collect]`
+                if fields.peek(token::Bracket) {
+                    let convert;
+                    bracketed!(convert in fields);
+                    convert.parse::<Ident>()?;
+                }
+
+                fields.parse::<Token![,]>()?;
+            }
+        }
+
+        Ok(PayloadMacro)
+    }
+}
+
+/// Body of `macro_rules!`, bodies of `(@method ...)` arms are parsed as trait
+/// items.
+struct MacroRules;
+
+impl Parse for MacroRules {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        while !input.is_empty() {
+            let matcher;
+            parenthesized!(matcher in input);
+            let is_method = matcher.peek(Token![@]);
+            matcher.parse::<TokenStream>()?;
+
+            input.parse::<Token![=>]>()?;
+
+            let body;
+            braced!(body in input);
+            let body = body.parse::<TokenStream>()?;
+
+            if is_method {
+                let body = replace_macro_vars(body);
+                syn::parse2::<syn::ItemTrait>(quote! { trait __Cg { #body } })?;
+            }
+
+            input.parse::<Token![;]>()?;
+        }
+
+        Ok(MacroRules)
+    }
+}
+
+/// Replaces `$name` with `__cg_name`, so the tokens can be parsed as Rust.
+fn replace_macro_vars(tokens: TokenStream) -> TokenStream {
+    let mut res = Vec::new();
+    let mut tokens = tokens.into_iter().peekable();
+
+    while let Some(tt) = tokens.next() {
+        match tt {
+            TokenTree::Punct(p) if p.as_char() == '$' => match tokens.peek() {
+                Some(TokenTree::Ident(name)) => {
+                    let mut name = format_ident!("__cg_{}", name);
+                    name.set_span(p.span());
+                    res.push(TokenTree::Ident(name));
+                    tokens.next();
+                }
+                _ => res.push(TokenTree::Punct(p)),
+            },
+            TokenTree::Group(g) => {
+                let mut group =
+                    proc_macro2::Group::new(g.delimiter(), replace_macro_vars(g.stream()));
+                group.set_span(g.span());
+                res.push(TokenTree::Group(group));
+            }
+            tt => res.push(tt),
+        }
+    }
+
+    res.into_iter().collect()
+}